@@ -0,0 +1,30 @@
+// Returns the value following the given flag, e.g. `--input path` returns `path`
+pub(crate) fn get_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(|value| value.as_str())
+}
+
+// Returns the two values following the flag, e.g. `--diff a b` returns `(a, b)`
+pub(crate) fn get_pair<'a>(args: &'a [String], flag: &str) -> Option<(&'a str, &'a str)> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    match (args.get(index + 1), args.get(index + 2)) {
+        (Some(first), Some(second)) => Some((first.as_str(), second.as_str())),
+        _ => None,
+    }
+}
+
+pub(crate) fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+// Returns the value following every occurrence of the flag, e.g. for repeated `--bag` options
+pub(crate) fn get_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.iter()
+        .enumerate()
+        .filter(|(_, arg)| *arg == flag)
+        .filter_map(|(index, _)| args.get(index + 1))
+        .map(|value| value.as_str())
+        .collect()
+}
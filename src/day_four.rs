@@ -25,13 +25,11 @@ impl Card {
 
         let (winning_numbers_str, card_numbers_str) = game_str.split_once('|').unwrap();
         let winning_numbers: Vec<u32> = winning_numbers_str
-            .trim()
             .split_whitespace()
             .map(|num_str| num_str.parse::<u32>().unwrap())
             .collect();
 
         let card_numbers: Vec<u32> = card_numbers_str
-            .trim()
             .split_whitespace()
             .map(|num_str| num_str.trim().parse::<u32>().unwrap())
             .collect();
@@ -71,7 +69,7 @@ struct CardCopySet {
 }
 
 impl CardCopySet {
-    fn create_card_map(cards: &Vec<Card>) -> HashMap<u32, Card> {
+    fn create_card_map(cards: &[Card]) -> HashMap<u32, Card> {
         let mut card_map = HashMap::new();
         cards.iter().for_each(|card| {
             card_map.insert(card.id, card.clone());
@@ -79,7 +77,7 @@ impl CardCopySet {
 
         card_map
     }
-    fn create_card_counts(cards: &Vec<Card>) -> HashMap<u32, u32> {
+    fn create_card_counts(cards: &[Card]) -> HashMap<u32, u32> {
         let mut card_map = HashMap::new();
         cards.iter().for_each(|card| {
             card_map.insert(card.id, 1);
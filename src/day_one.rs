@@ -1,4 +1,9 @@
-use crate::inputs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::Instant;
+
+use crate::{ansi, args, inputs};
 
 const INITIAL_VALID_STRINGS: [&str; 10] = [
     "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
@@ -35,68 +40,410 @@ fn map_to_int_value(val: &str) -> Option<u32> {
     }
 }
 
-fn find_first_match(str: &String, values: Vec<&str>) -> Option<String> {
-    // Iterate through slices of the whole string, from the first letter, increasing in
-    // size each iteration until a match is found with one of the values
-    let mut index: usize = 1;
-    while index <= str.len() {
-        for v in values.clone().into_iter() {
+#[derive(Debug, Clone)]
+struct TokenMatch {
+    start: usize,
+    token: String,
+}
+
+impl TokenMatch {
+    fn new(start: usize, token: String) -> TokenMatch {
+        TokenMatch { start, token }
+    }
+
+    fn end(self: &Self) -> usize {
+        self.start + self.token.len()
+    }
+
+    fn contains(self: &Self, index: usize) -> bool {
+        index >= self.start && index < self.end()
+    }
+}
+
+fn find_first_match_position(str: &str, values: &[&str]) -> Option<TokenMatch> {
+    // Iterate through slices of the whole string, from the first character, increasing in
+    // size each iteration until a match is found with one of the values. Slices end on
+    // character boundaries so multi-byte characters can't split them
+    for (offset, char) in str.char_indices() {
+        let index = offset + char.len_utf8();
+        for v in values.iter() {
             let (first, _) = str.split_at(index);
             if first.contains(v) {
-                return Some(v.to_string());
+                // The match wasn't in the previous slice so it must end at this index
+                return Some(TokenMatch::new(index - v.len(), v.to_string()));
             }
         }
-        index += 1;
     }
 
     return None;
 }
 
+fn find_first_match(str: &str, values: &[&str]) -> Option<String> {
+    find_first_match_position(str, values).map(|token_match| token_match.token)
+}
+
 fn reverse_str(str: &str) -> String {
     str.chars().rev().collect()
 }
 
-fn find_last_match(str: &String, values: Vec<&str>) -> Option<String> {
+fn find_last_match_position(str: &str, values: &[&str]) -> Option<TokenMatch> {
     // Reverse all strings then do the normal forward match and reverse the found string
     let reversed_value_strings: Vec<String> = values.iter().map(|val| reverse_str(val)).collect();
     let reversed_values: Vec<&str> = reversed_value_strings
         .iter()
         .map(|val| val.as_str())
         .collect();
-    let matched_str = find_first_match(&reverse_str(str), reversed_values);
-    match matched_str {
-        Some(val) => Some(reverse_str(&val)),
-        None => None,
-    }
+    let matched = find_first_match_position(&reverse_str(str), &reversed_values)?;
+    Some(TokenMatch::new(
+        str.len() - matched.end(),
+        reverse_str(&matched.token),
+    ))
 }
 
-fn parse_configuration_value(configuration_string: &String, valid_strings: Vec<&str>) -> Option<u32> {
-    let first = map_to_int_value(&find_first_match(configuration_string, valid_strings.clone())?);
-    let last = map_to_int_value(&find_last_match(configuration_string, valid_strings.clone())?);
+fn find_last_match(str: &str, values: &[&str]) -> Option<String> {
+    find_last_match_position(str, values).map(|token_match| token_match.token)
+}
+
+fn parse_configuration_value_general(
+    configuration_string: &str,
+    valid_strings: &[&str],
+) -> Option<u32> {
+    let first = map_to_int_value(&find_first_match(configuration_string, valid_strings)?);
+    let last = map_to_int_value(&find_last_match(configuration_string, valid_strings)?);
 
     return Some(first? * 10 + last?);
 }
 
-pub(crate) fn part_one() {
-    println!("Day One, Part One");
-    let lines = inputs::read_inputs_from_file("./inputs/day_one.txt").unwrap();
-    let valid_strings = Vec::from(INITIAL_VALID_STRINGS);
-    let total: u32 = lines
+fn find_first_digit(bytes: &[u8], digits: &[bool; 10]) -> Option<u32> {
+    bytes
+        .iter()
+        .filter(|byte| byte.is_ascii_digit())
+        .map(|byte| u32::from(byte - b'0'))
+        .find(|value| digits[*value as usize])
+}
+
+fn find_last_digit(bytes: &[u8], digits: &[bool; 10]) -> Option<u32> {
+    bytes
+        .iter()
+        .rev()
+        .filter(|byte| byte.is_ascii_digit())
+        .map(|byte| u32::from(byte - b'0'))
+        .find(|value| digits[*value as usize])
+}
+
+fn parse_configuration_value_digits(
+    configuration_string: &str,
+    digits: &[bool; 10],
+) -> Option<u32> {
+    let bytes = configuration_string.as_bytes();
+    let first = find_first_digit(bytes, digits)?;
+    let last = find_last_digit(bytes, digits)?;
+
+    Some(first * 10 + last)
+}
+
+// The set of strings a calibration value is built from. When every string is a single ASCII
+// digit the lines can be scanned as bytes rather than matched as substrings
+#[derive(Debug, Clone)]
+enum Vocabulary<'a> {
+    Digits([bool; 10]),
+    Strings(&'a [&'a str]),
+}
+
+impl<'a> Vocabulary<'a> {
+    fn new(valid_strings: &'a [&'a str]) -> Vocabulary<'a> {
+        let mut digits = [false; 10];
+        for val in valid_strings.iter() {
+            match val.as_bytes() {
+                [byte] if byte.is_ascii_digit() => digits[usize::from(byte - b'0')] = true,
+                _ => return Vocabulary::Strings(valid_strings),
+            }
+        }
+
+        Vocabulary::Digits(digits)
+    }
+
+    fn parse_value(self: &Self, configuration_string: &str) -> Option<u32> {
+        match self {
+            Vocabulary::Digits(digits) => {
+                parse_configuration_value_digits(configuration_string, digits)
+            }
+            Vocabulary::Strings(valid_strings) => {
+                parse_configuration_value_general(configuration_string, valid_strings)
+            }
+        }
+    }
+}
+
+fn parse_configuration_value(configuration_string: &str, valid_strings: &[&str]) -> Option<u32> {
+    Vocabulary::new(valid_strings).parse_value(configuration_string)
+}
+
+// Splits the file into roughly equal byte ranges, moving each boundary forward to just past
+// the next newline so that no line is shared between two chunks
+fn find_chunk_boundaries(
+    file_path: &str,
+    chunk_count: u64,
+) -> Result<Vec<(u64, u64)>, std::io::Error> {
+    let mut file = File::open(file_path)?;
+    let file_len = file.metadata()?.len();
+    let chunk_count = chunk_count.max(1);
+
+    let mut boundaries: Vec<u64> = vec![0];
+    for i in 1..chunk_count {
+        let approximate = file_len * i / chunk_count;
+        let previous = *boundaries.last().unwrap();
+        if approximate <= previous {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start(approximate))?;
+        let mut skipped: Vec<u8> = Vec::new();
+        let skipped_len = BufReader::new(&mut file).read_until(b'\n', &mut skipped)?;
+        let boundary = approximate + u64::try_from(skipped_len).unwrap();
+        if boundary > previous && boundary < file_len {
+            boundaries.push(boundary);
+        }
+    }
+    boundaries.push(file_len);
+
+    Ok(boundaries
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect())
+}
+
+// Sums the calibration values for the lines in a single byte range, reusing one line buffer
+fn sum_calibration_chunk(
+    file_path: &str,
+    start: u64,
+    end: u64,
+    valid_strings: &[&str],
+) -> Result<u64, std::io::Error> {
+    let vocabulary = Vocabulary::new(valid_strings);
+    let mut file = File::open(file_path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(file.take(end - start));
+
+    let mut total: u64 = 0;
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if !trimmed.is_empty() {
+            let value = vocabulary.parse_value(trimmed).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("No calibration value in line: {}", trimmed),
+                )
+            })?;
+            total += u64::from(value);
+        }
+        line.clear();
+    }
+
+    Ok(total)
+}
+
+// Sums the calibration values of every line in the file using one thread per core, without
+// holding more than a line per thread in memory
+pub(crate) fn sum_calibration_file(
+    file_path: &str,
+    valid_strings: &[&str],
+) -> Result<u64, std::io::Error> {
+    let thread_count = thread::available_parallelism().map_or(1, |count| count.get());
+    let chunks = find_chunk_boundaries(file_path, u64::try_from(thread_count).unwrap())?;
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|(start, end)| {
+                scope.spawn(move || sum_calibration_chunk(file_path, *start, *end, valid_strings))
+            })
+            .collect();
+
+        // Join every worker before summing, as the scope panics on any left unjoined
+        let results: Vec<Result<u64, std::io::Error>> = handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(std::io::Error::other("A calibration worker panicked")))
+            })
+            .collect();
+
+        results.into_iter().sum()
+    })
+}
+
+const DEFAULT_INPUT: &str = "./inputs/day_one.txt";
+
+// Times the byte scan against the general substring matcher over the same lines
+fn benchmark(file_path: &str) -> Result<(), String> {
+    let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+    let vocabulary = Vocabulary::new(&INITIAL_VALID_STRINGS);
+
+    let general_start = Instant::now();
+    let general_total: u64 = lines
+        .iter()
+        .filter_map(|line| parse_configuration_value_general(line, &INITIAL_VALID_STRINGS))
+        .map(u64::from)
+        .sum();
+    let general_elapsed = general_start.elapsed();
+
+    let fast_start = Instant::now();
+    let fast_total: u64 = lines
         .iter()
-        .map(|line| parse_configuration_value(line, valid_strings.clone()))
-        .map(|opt| opt.unwrap())
+        .filter_map(|line| vocabulary.parse_value(line))
+        .map(u64::from)
         .sum();
+    let fast_elapsed = fast_start.elapsed();
+
+    println!("Benchmarking {} lines", lines.len());
+    println!("General path: {} in {:?}", general_total, general_elapsed);
+    println!("Digit path:   {} in {:?}", fast_total, fast_elapsed);
+
+    if general_total != fast_total {
+        return Err(String::from(
+            "Digit path total does not match the general path",
+        ));
+    }
+
+    Ok(())
+}
+
+// Wraps the first and last matched tokens in colour codes, using a third colour for any
+// characters shared by both (e.g. the "t" of "eightwo")
+fn highlight_matches(line: &str, first: &TokenMatch, last: &TokenMatch) -> String {
+    let mut highlighted = String::new();
+    let mut current_colour: Option<&str> = None;
+
+    line.char_indices().for_each(|(index, char)| {
+        let colour = match (first.contains(index), last.contains(index)) {
+            (true, true) => Some(ansi::MAGENTA),
+            (true, false) => Some(ansi::GREEN),
+            (false, true) => Some(ansi::CYAN),
+            (false, false) => None,
+        };
+
+        if colour != current_colour {
+            if current_colour.is_some() {
+                highlighted.push_str(ansi::RESET);
+            }
+            if let Some(code) = colour {
+                highlighted.push_str(ansi::BOLD);
+                highlighted.push_str(code);
+            }
+            current_colour = colour;
+        }
+        highlighted.push(char);
+    });
+    if current_colour.is_some() {
+        highlighted.push_str(ansi::RESET);
+    }
+
+    highlighted
+}
+
+// Prints every line with its chosen tokens highlighted, the value they make and the running
+// total. With `only_disagreements` set, only lines where part one and two differ are shown
+fn explain(
+    file_path: &str,
+    valid_strings: &[&str],
+    only_disagreements: bool,
+) -> Result<(), String> {
+    let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+    let mut total: u64 = 0;
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let first = find_first_match_position(line, valid_strings);
+        let last = find_last_match_position(line, valid_strings);
+
+        let value = match (&first, &last) {
+            (Some(first), Some(last)) => map_to_int_value(&first.token)
+                .zip(map_to_int_value(&last.token))
+                .map(|(first_value, last_value)| first_value * 10 + last_value),
+            _ => None,
+        };
+        total += u64::from(value.unwrap_or(0));
+
+        let part_one_value = parse_configuration_value(line, &INITIAL_VALID_STRINGS);
+        let part_two_value = parse_configuration_value(line, &VALID_STRINGS);
+        if only_disagreements && part_one_value == part_two_value {
+            continue;
+        }
+
+        let highlighted = match (&first, &last) {
+            (Some(first), Some(last)) => highlight_matches(line, first, last),
+            _ => line.clone(),
+        };
+        let value_str = value.map_or(String::from("no value"), |val| val.to_string());
+
+        if only_disagreements {
+            println!(
+                "{:>5}: {} -> {} (part one {:?}, part two {:?}), total {}",
+                line_index + 1,
+                highlighted,
+                value_str,
+                part_one_value,
+                part_two_value,
+                total
+            );
+        } else {
+            println!(
+                "{:>5}: {} -> {}, total {}",
+                line_index + 1,
+                highlighted,
+                value_str,
+                total
+            );
+        }
+    }
+
+    println!("Total sum: {}", total);
+    Ok(())
+}
+
+// Runs both parts against `--input <path>` (or the default input), e.g. `day1 --input big.txt`.
+// Pass `--bench` to compare the digit and general matching paths instead, or `--explain`
+// (with optional `--part-one` and `--disagreements`) to annotate each line
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let file_path = args::get_value(args, "--input").unwrap_or(DEFAULT_INPUT);
+
+    if args::has_flag(args, "--bench") {
+        return benchmark(file_path);
+    }
+
+    if args::has_flag(args, "--explain") {
+        let valid_strings: &[&str] = match args::has_flag(args, "--part-one") {
+            true => &INITIAL_VALID_STRINGS,
+            false => &VALID_STRINGS,
+        };
+        return explain(
+            file_path,
+            valid_strings,
+            args::has_flag(args, "--disagreements"),
+        );
+    }
+
+    let part_one_total =
+        sum_calibration_file(file_path, &INITIAL_VALID_STRINGS).map_err(|e| e.to_string())?;
+    println!("Part one total sum: {}", part_one_total);
+
+    let part_two_total =
+        sum_calibration_file(file_path, &VALID_STRINGS).map_err(|e| e.to_string())?;
+    println!("Part two total sum: {}", part_two_total);
+
+    Ok(())
+}
+
+pub(crate) fn part_one() {
+    println!("Day One, Part One");
+    let total = sum_calibration_file(DEFAULT_INPUT, &INITIAL_VALID_STRINGS).unwrap();
     println!("Total sum: {:}", total);
 }
 
 pub(crate) fn part_two() {
     println!("Day One, Part Two");
-    let lines = inputs::read_inputs_from_file("./inputs/day_one.txt").unwrap();
-    let valid_strings = Vec::from(VALID_STRINGS);
-    let total: u32 = lines
-        .iter()
-        .map(|replaced_str| parse_configuration_value(&replaced_str, valid_strings.clone()))
-        .map(|opt| opt.unwrap())
-        .sum();
+    let total = sum_calibration_file(DEFAULT_INPUT, &VALID_STRINGS).unwrap();
     println!("Total sum: {:}", total);
 }
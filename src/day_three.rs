@@ -1,72 +1,147 @@
-use crate::inputs;
-use std::collections::HashMap;
+use crate::grid::{Grid, Point};
+use crate::{args, inputs};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::str::FromStr;
+
+mod components;
+mod diff;
+mod incremental;
+mod render;
+mod sparse;
+mod svg;
 
 const IGNORED: [char; 1] = ['.'];
-const SYMBOLS: [char; 10] = ['&', '*', '/', '+', '-', '@', '=', '%', '#', '$'];
 const VALUES: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
 const GEARS: [char; 1] = ['*'];
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SymbolSet {
+    // Anything that isn't a value or ignored
+    Auto,
+    Explicit(Vec<char>),
+}
+
+impl SymbolSet {
+    fn contains(self: &Self, char: char, values: &[char], ignored: &[char]) -> bool {
+        match self {
+            SymbolSet::Auto => !values.contains(&char) && !ignored.contains(&char),
+            SymbolSet::Explicit(symbols) => symbols.contains(&char),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SchematicWarning {
+    // A character that is neither a value, ignored nor a symbol. It separates numbers but
+    // doesn't make them part numbers
+    UnknownCharacter {
+        character: char,
+        first_seen: Point,
+        occurrences: usize,
+    },
+}
+
+impl fmt::Display for SchematicWarning {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchematicWarning::UnknownCharacter {
+                character,
+                first_seen,
+                occurrences,
+            } => write!(
+                f,
+                "Unknown character '{}' seen {} times, first at row {} column {}",
+                character, occurrences, first_seen.row, first_seen.column
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct RawSchematic {
-    lines: Vec<String>,
+    grid: Grid<char>,
     ignored: Vec<char>,
-    symbols: Vec<char>,
+    symbols: SymbolSet,
     values: Vec<char>,
+    neighbourhood: Neighbourhood,
 }
 
 impl RawSchematic {
     fn new(lines: Vec<String>) -> RawSchematic {
         RawSchematic {
-            lines,
+            grid: Grid::from_lines(&lines, IGNORED[0]),
             ignored: Vec::from(IGNORED),
-            symbols: Vec::from(SYMBOLS),
+            symbols: SymbolSet::Auto,
             values: Vec::from(VALUES),
+            neighbourhood: Neighbourhood::default(),
         }
     }
 
-    // Returns true if the given rect contains any of the provided characters
-    fn rect_contains_chars(self: &Self, rect: &Rect, chars: &Vec<char>) -> bool {
-        for col in rect.left..(rect.right + 1) {
-            for row in rect.top..(rect.bottom + 1) {
-                match self.is_char(col, row, chars) {
-                    true => return true,
-                    _ => (),
-                }
-            }
+    fn with_neighbourhood(self: Self, neighbourhood: Neighbourhood) -> RawSchematic {
+        RawSchematic {
+            neighbourhood,
+            ..self
         }
-
-        false
     }
 
-    fn get_contains_chars_or_none(
-        self: &Self,
-        col: isize,
-        row: isize,
-        chars: &Vec<char>,
-    ) -> Option<bool> {
-        let symbol = self
-            .lines
-            .get(usize::try_from(row).ok()?)?
-            .chars()
-            .collect::<Vec<char>>()
-            .get(usize::try_from(col).ok()?)?
-            .clone();
-
-        Some(chars.contains(&symbol))
-    }
-
-    fn is_char(self: &Self, col: isize, row: isize, chars: &Vec<char>) -> bool {
-        if col < 0 {
-            return false;
+    fn with_symbols(lines: Vec<String>, symbols: Vec<char>) -> RawSchematic {
+        RawSchematic {
+            symbols: SymbolSet::Explicit(symbols),
+            ..RawSchematic::new(lines)
         }
+    }
 
-        if row < 0 {
-            return false;
-        }
+    fn is_symbol(self: &Self, char: char) -> bool {
+        self.symbols.contains(char, &self.values, &self.ignored)
+    }
+
+    fn is_unknown(self: &Self, char: char) -> bool {
+        !self.values.contains(&char) && !self.ignored.contains(&char) && !self.is_symbol(char)
+    }
 
-        match self.get_contains_chars_or_none(col, row, chars) {
-            Some(val) => return val,
-            None => return false,
+    // Each unknown character once, in the order they first appear
+    fn get_warnings(self: &Self) -> Vec<SchematicWarning> {
+        let mut unknown: BTreeMap<char, (Point, usize)> = BTreeMap::new();
+        self.grid
+            .iter()
+            .filter(|(_, char)| self.is_unknown(**char))
+            .for_each(|(point, char)| {
+                unknown.entry(*char).or_insert((point, 0)).1 += 1;
+            });
+
+        let mut warnings: Vec<(Point, SchematicWarning)> = unknown
+            .into_iter()
+            .map(|(character, (first_seen, occurrences))| {
+                (
+                    first_seen,
+                    SchematicWarning::UnknownCharacter {
+                        character,
+                        first_seen,
+                        occurrences,
+                    },
+                )
+            })
+            .collect();
+        warnings.sort_by_key(|(first_seen, _)| *first_seen);
+
+        warnings.into_iter().map(|(_, warning)| warning).collect()
+    }
+
+    // A gear has to be a symbol as well as one of the rule's characters, so a `*` left out of
+    // an explicit symbol set never counts
+    fn is_gear_at(self: &Self, point: Point, rule: &GearRule) -> bool {
+        self.grid
+            .get(point)
+            .is_some_and(|char| self.is_symbol(*char) && rule.symbols.contains(char))
+    }
+
+    fn is_symbol_at(self: &Self, col: isize, row: isize) -> bool {
+        match self.grid.get(Point::new(row, col)) {
+            Some(char) => self.is_symbol(*char),
+            None => false,
         }
     }
 
@@ -74,130 +149,224 @@ impl RawSchematic {
         let mut candidates: Vec<PartNumberCandidate> = Vec::new();
         let mut current_candidate: Option<PartNumberCandidate> = None;
 
-        self.lines
-            .iter()
-            .enumerate()
-            .for_each(|(line_index, line)| {
-                line.chars().enumerate().for_each(|(index, char)| {
-                    if SYMBOLS.contains(&char) | IGNORED.contains(&char) {
-                        // If we are currently constructing a candidate, stop
-                        if let Some(curr) = current_candidate {
-                            candidates.push(curr);
-                            current_candidate = None;
+        self.grid.rows().enumerate().for_each(|(line_index, line)| {
+            line.iter().copied().enumerate().for_each(|(index, char)| {
+                if self.values.contains(&char) {
+                    let value = char as u32 - 0x30;
+                    match current_candidate {
+                        // If we are currently constructing a candidate, append a value to it
+                        Some(curr) => {
+                            current_candidate = Some(curr.append_digit(value));
                         }
-                    } else if VALUES.contains(&char) {
-                        let value = char as u32 - 0x30;
-                        match current_candidate {
-                            // If we are currently constructing a candidate, append a value to it
-                            Some(curr) => {
-                                current_candidate = Some(curr.append_digit(value));
-                            }
-                            // If we are not, start constructing a new candidate
-                            None => {
-                                current_candidate =
-                                    Some(PartNumberCandidate::new(line_index, index, 1, value));
-                            }
+                        // If we are not, start constructing a new candidate
+                        None => {
+                            current_candidate =
+                                Some(PartNumberCandidate::new(line_index, index, 1, value));
                         }
-                    } else {
-                        println!("Unmatched char {:}", char);
                     }
-                });
-
-                if let Some(curr) = current_candidate {
+                } else if let Some(curr) = current_candidate {
+                    // Anything else ends the candidate we are currently constructing
                     candidates.push(curr);
                     current_candidate = None;
                 }
             });
 
-        candidates
-    }
+            if let Some(curr) = current_candidate {
+                candidates.push(curr);
+                current_candidate = None;
+            }
+        });
 
-    fn candidate_valid(self: &Self, candidate: &PartNumberCandidate) -> bool {
-        let outline = candidate.get_outline().unwrap();
-        self.rect_contains_chars(&outline, &self.symbols)
+        candidates
     }
 
-    fn get_part_numbers(self: &Self) -> Vec<PartNumber> {
-        self.find_part_number_candidates()
+    fn get_symbols(self: &Self) -> Vec<Symbol> {
+        self.grid
             .iter()
-            .filter(|candidate| self.candidate_valid(candidate))
-            .map(|candidate| PartNumber::from_candidate(candidate))
+            .filter(|(_, char)| self.is_symbol(**char))
+            .map(|(point, char)| Symbol::new(point, *char))
             .collect()
     }
+}
 
-    fn get_part_proximity_map(self: &Self) -> HashMap<String, Vec<PartNumber>> {
-        let mut proximity_map: HashMap<String, Vec<PartNumber>> = HashMap::new();
-
-        self.get_part_numbers().iter().for_each(|part_number| {
-            let rect = part_number.get_outline().unwrap();
-            for col in rect.left..(rect.right + 1) {
-                for row in rect.top..(rect.bottom + 1) {
-                    let key = format!("{}-{}", col, row);
-                    if !proximity_map.contains_key(&key) {
-                        proximity_map.insert(key.clone(), vec![]);
-                    }
+// Records which number candidate covers each cell, so finding the parts around a cell is a
+// lookup of its neighbours rather than a search
+#[derive(Debug, Clone)]
+struct PartIndex<'a> {
+    raw: &'a RawSchematic,
+    candidates: Vec<PartNumberCandidate>,
+    cell_candidates: Grid<Option<usize>>,
+}
 
-                    let mut current = proximity_map.get(&key).unwrap().clone();
-                    current.push(part_number.clone());
-                    proximity_map.insert(key, current);
+impl<'a> PartIndex<'a> {
+    fn new(raw: &'a RawSchematic) -> PartIndex<'a> {
+        let candidates = raw.find_part_number_candidates();
+        let mut cell_candidates = Grid::new(raw.grid.width(), raw.grid.height(), None);
+        candidates
+            .iter()
+            .enumerate()
+            .for_each(|(candidate_id, candidate)| {
+                for offset in 0..candidate.length {
+                    let point = Point::new(
+                        candidate.start_row as isize,
+                        (candidate.start_column + offset) as isize,
+                    );
+                    cell_candidates.set(point, Some(candidate_id));
                 }
-            }
-        });
+            });
 
-        proximity_map
+        PartIndex {
+            raw,
+            candidates,
+            cell_candidates,
+        }
+    }
+
+    // IDs of every number candidate covering the point or any of its neighbours
+    fn candidates_touching(self: &Self, point: Point) -> Vec<usize> {
+        self.raw
+            .neighbourhood
+            .ids_touching(&self.cell_candidates, point)
     }
+}
+
+#[derive(Debug, Clone)]
+struct Schematic {
+    raw: RawSchematic,
+    // Every number, whether or not it is a part number
+    numbers: Vec<PartNumber>,
+    symbols: Vec<Symbol>,
+    // Both sides of the number to symbol adjacency graph, indexed by number and symbol ID
+    number_symbols: Vec<Vec<usize>>,
+    symbol_numbers: Vec<Vec<usize>>,
+    parts: Vec<PartNumber>,
+    gears: Vec<Gear>,
+}
+
+impl Schematic {
+    // Builds the whole model by visiting each symbol once and looking up the numbers around it
+    fn parse(raw: RawSchematic) -> Schematic {
+        let index = PartIndex::new(&raw);
+        let numbers: Vec<PartNumber> = index
+            .candidates
+            .iter()
+            .map(PartNumber::from_candidate)
+            .collect();
+        let symbols = raw.get_symbols();
 
-    fn get_gear_candidates(self: &Self) -> Vec<GearCandidate> {
-        let mut candidates: Vec<GearCandidate> = Vec::new();
-        self.lines
+        let mut number_symbols: Vec<Vec<usize>> = vec![vec![]; numbers.len()];
+        let symbol_numbers: Vec<Vec<usize>> = symbols
             .iter()
             .enumerate()
-            .for_each(|(line_index, line)| {
-                line.chars().enumerate().for_each(|(index, char)| {
-                    if GEARS.contains(&char) {
-                        candidates.push(GearCandidate::new(line_index, index))
-                    }
-                })
-            });
+            .map(|(symbol_id, symbol)| {
+                let number_ids = index.candidates_touching(symbol.position);
+                number_ids
+                    .iter()
+                    .for_each(|number_id| number_symbols[*number_id].push(symbol_id));
+                number_ids
+            })
+            .collect();
 
-        candidates
+        let parts: Vec<PartNumber> = numbers
+            .iter()
+            .zip(number_symbols.iter())
+            .filter(|(_, symbol_ids)| !symbol_ids.is_empty())
+            .map(|(number, _)| number.clone())
+            .collect();
+
+        let mut schematic = Schematic {
+            raw,
+            numbers,
+            symbols,
+            number_symbols,
+            symbol_numbers,
+            parts,
+            gears: vec![],
+        };
+        schematic.gears = schematic.gears_for_rule(&GearRule::default());
+
+        schematic
     }
 
-    fn get_gears(self: &Self) -> Vec<Gear> {
-        let proximity_map = self.get_part_proximity_map();
-        let candidates = self.get_gear_candidates();
+    // Every symbol the rule accepts along with the numbers around it
+    fn gears_for_rule(self: &Self, rule: &GearRule) -> Vec<Gear> {
+        self.symbols
+            .iter()
+            .zip(self.symbol_numbers.iter())
+            .filter(|(symbol, number_ids)| {
+                self.raw.is_gear_at(symbol.position, rule)
+                    && rule.adjacency.matches(number_ids.len())
+            })
+            .map(|(symbol, number_ids)| {
+                let values: Vec<u64> = number_ids
+                    .iter()
+                    .map(|number_id| u64::from(self.numbers[*number_id].value))
+                    .collect();
+                Gear::new(
+                    symbol.position.row as usize,
+                    symbol.position.column as usize,
+                    rule.combine.apply(&values),
+                    number_ids.clone(),
+                )
+            })
+            .collect()
+    }
 
-        candidates
+    fn is_part(self: &Self, number_id: usize) -> bool {
+        !self.number_symbols[number_id].is_empty()
+    }
+
+    // Part numbers next to at least one symbol of the given kind
+    fn parts_touching_kind(self: &Self, kind: char) -> Vec<&PartNumber> {
+        self.numbers
             .iter()
-            .flat_map(|candidate| {
-                let key = format!("{}-{}", candidate.column, candidate.row);
-                if !proximity_map.contains_key(&key) {
-                    return None;
-                } else {
-                    let part_numbers = proximity_map.get(&key).unwrap();
-                    if part_numbers.len() != 2 {
-                        return None;
-                    }
+            .zip(self.number_symbols.iter())
+            .filter(|(_, symbol_ids)| {
+                symbol_ids
+                    .iter()
+                    .any(|symbol_id| self.symbols[*symbol_id].kind == kind)
+            })
+            .map(|(number, _)| number)
+            .collect()
+    }
 
-                    Some(candidate.to_gear(
-                        part_numbers.get(0).unwrap().value * part_numbers.get(1).unwrap().value,
-                    ))
-                }
+    fn symbols_without_parts(self: &Self) -> Vec<&Symbol> {
+        self.symbols
+            .iter()
+            .zip(self.symbol_numbers.iter())
+            .filter(|(_, number_ids)| number_ids.is_empty())
+            .map(|(symbol, _)| symbol)
+            .collect()
+    }
+
+    // Numbers next to symbols of at least two different kinds, e.g. both a `*` and a `#`
+    fn numbers_touching_multiple_kinds(self: &Self) -> Vec<&PartNumber> {
+        self.numbers
+            .iter()
+            .zip(self.number_symbols.iter())
+            .filter(|(_, symbol_ids)| {
+                let mut kinds: Vec<char> = symbol_ids
+                    .iter()
+                    .map(|symbol_id| self.symbols[*symbol_id].kind)
+                    .collect();
+                kinds.sort();
+                kinds.dedup();
+                kinds.len() > 1
             })
-            .collect::<Vec<Gear>>()
+            .map(|(number, _)| number)
+            .collect()
     }
 }
 
-#[derive(Debug, Clone)]
-struct Schematic {
-    raw: RawSchematic,
-    parts: Vec<PartNumber>,
-    gears: Vec<Gear>,
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Symbol {
+    position: Point,
+    kind: char,
 }
 
-impl Schematic {
-    fn new(raw: RawSchematic, parts: Vec<PartNumber>, gears: Vec<Gear>) -> Schematic {
-        Schematic { raw, parts, gears }
+impl Symbol {
+    fn new(position: Point, kind: char) -> Symbol {
+        Symbol { position, kind }
     }
 }
 
@@ -233,16 +402,19 @@ impl PartNumberCandidate {
         )
     }
 
-    fn get_outline(self: &Self) -> Option<Rect> {
+    // The smallest rect holding every cell the neighbourhood reaches from the number, which
+    // may extend past the grid edges
+    fn get_outline(self: &Self, neighbourhood: &Neighbourhood) -> Option<Rect> {
         let i_start_row = isize::try_from(self.start_row).ok()?;
         let i_start_col = isize::try_from(self.start_column).ok()?;
         let i_length = isize::try_from(self.length).ok()?;
+        let i_radius = isize::try_from(neighbourhood.radius).ok()?;
 
         Some(Rect {
-            top: i_start_row - 1,
-            left: i_start_col - 1,
-            bottom: i_start_row + 1,
-            right: i_start_col + i_length,
+            top: i_start_row - i_radius,
+            left: i_start_col - i_radius,
+            bottom: i_start_row + i_radius,
+            right: i_start_col + i_length - 1 + i_radius,
         })
     }
 }
@@ -274,46 +446,147 @@ impl PartNumber {
         }
     }
 
-    fn get_outline(self: &Self) -> Option<Rect> {
+    // The smallest rect holding every cell the neighbourhood reaches from the number, which
+    // may extend past the grid edges
+    fn get_outline(self: &Self, neighbourhood: &Neighbourhood) -> Option<Rect> {
         let i_start_row = isize::try_from(self.start_row).ok()?;
         let i_start_col = isize::try_from(self.start_column).ok()?;
         let i_length = isize::try_from(self.length).ok()?;
+        let i_radius = isize::try_from(neighbourhood.radius).ok()?;
 
         Some(Rect {
-            top: i_start_row - 1,
-            left: i_start_col - 1,
-            bottom: i_start_row + 1,
-            right: i_start_col + i_length,
+            top: i_start_row - i_radius,
+            left: i_start_col - i_radius,
+            bottom: i_start_row + i_radius,
+            right: i_start_col + i_length - 1 + i_radius,
         })
     }
 }
 
 #[derive(Debug, Clone)]
-struct GearCandidate {
+struct Gear {
     row: usize,
     column: usize,
+    ratio: u64,
+    // IDs of the numbers combined into the ratio
+    part_ids: Vec<usize>,
 }
 
-impl GearCandidate {
-    fn new(row: usize, column: usize) -> GearCandidate {
-        GearCandidate { row, column }
+impl Gear {
+    fn new(row: usize, column: usize, ratio: u64, part_ids: Vec<usize>) -> Gear {
+        Gear {
+            row,
+            column,
+            ratio,
+            part_ids,
+        }
     }
+}
+
+// How many numbers must surround a gear
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdjacencyCount {
+    Exact(usize),
+    AtLeast(usize),
+    Range(usize, usize),
+}
 
-    fn to_gear(self: &Self, value: u32) -> Gear {
-        Gear::new(self.row, self.column, value)
+impl AdjacencyCount {
+    fn matches(self: &Self, count: usize) -> bool {
+        match self {
+            AdjacencyCount::Exact(exact) => count == *exact,
+            AdjacencyCount::AtLeast(min) => count >= *min,
+            AdjacencyCount::Range(min, max) => count >= *min && count <= *max,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
-struct Gear {
-    row: usize,
-    column: usize,
-    ratio: u32,
+impl FromStr for AdjacencyCount {
+    type Err = String;
+
+    fn from_str(count_str: &str) -> Result<AdjacencyCount, String> {
+        // Reference strings
+        // 2
+        // 2+
+        // 2-4
+        let parse_count = |str: &str| {
+            str.trim()
+                .parse::<usize>()
+                .map_err(|_e| format!("Invalid adjacency count '{}'", count_str))
+        };
+
+        if let Some(min_str) = count_str.strip_suffix('+') {
+            return Ok(AdjacencyCount::AtLeast(parse_count(min_str)?));
+        }
+
+        match count_str.split_once('-') {
+            Some((min_str, max_str)) => {
+                let (min, max) = (parse_count(min_str)?, parse_count(max_str)?);
+                if min > max {
+                    return Err(format!(
+                        "Invalid adjacency count '{}', the range is backwards",
+                        count_str
+                    ));
+                }
+                Ok(AdjacencyCount::Range(min, max))
+            }
+            None => Ok(AdjacencyCount::Exact(parse_count(count_str)?)),
+        }
+    }
 }
 
-impl Gear {
-    fn new(row: usize, column: usize, ratio: u32) -> Gear {
-        Gear { row, column, ratio }
+// How the numbers around a gear make its ratio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combine {
+    Product,
+    Sum,
+    Max,
+}
+
+impl Combine {
+    // Saturates rather than overflowing when many or large numbers surround a gear
+    fn apply(self: &Self, values: &[u64]) -> u64 {
+        let values = values.iter().copied();
+        match self {
+            Combine::Product => values.fold(1, u64::saturating_mul),
+            Combine::Sum => values.fold(0, u64::saturating_add),
+            Combine::Max => values.max().unwrap_or(0),
+        }
+    }
+}
+
+impl FromStr for Combine {
+    type Err = String;
+
+    fn from_str(combine_str: &str) -> Result<Combine, String> {
+        match combine_str {
+            "product" => Ok(Combine::Product),
+            "sum" => Ok(Combine::Sum),
+            "max" => Ok(Combine::Max),
+            _ => Err(format!(
+                "Invalid combine operation '{}', expected product, sum or max",
+                combine_str
+            )),
+        }
+    }
+}
+
+// Which symbols count as gears and how their ratios are worked out. The puzzle's rule is a
+// `*` next to exactly two numbers, multiplied together
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GearRule {
+    symbols: Vec<char>,
+    adjacency: AdjacencyCount,
+    combine: Combine,
+}
+
+impl Default for GearRule {
+    fn default() -> GearRule {
+        GearRule {
+            symbols: Vec::from(GEARS),
+            adjacency: AdjacencyCount::Exact(2),
+            combine: Combine::Product,
+        }
     }
 }
 
@@ -325,11 +598,489 @@ struct Rect {
     right: isize,
 }
 
+impl Rect {
+    // The part of the rect inside a grid of the given size, if any
+    fn clip(self: &Self, width: usize, height: usize) -> Option<Rect> {
+        let rect = Rect {
+            top: self.top.max(0),
+            left: self.left.max(0),
+            bottom: self.bottom.min(height as isize - 1),
+            right: self.right.min(width as isize - 1),
+        };
+
+        match rect.top <= rect.bottom && rect.left <= rect.right {
+            true => Some(rect),
+            false => None,
+        }
+    }
+
+    // Splits the rect where it crosses the grid edges and moves the pieces that hang over an
+    // edge to the opposite side, giving up to four rects inside the grid
+    fn wrap(self: &Self, width: usize, height: usize) -> Vec<Rect> {
+        let rows = Rect::wrap_range(self.top, self.bottom, height);
+        let columns = Rect::wrap_range(self.left, self.right, width);
+
+        rows.iter()
+            .flat_map(|(top, bottom)| {
+                columns.iter().map(|(left, right)| Rect {
+                    top: *top,
+                    left: *left,
+                    bottom: *bottom,
+                    right: *right,
+                })
+            })
+            .collect()
+    }
+
+    fn wrap_range(low: isize, high: isize, size: usize) -> Vec<(isize, isize)> {
+        let size = size as isize;
+        if size == 0 {
+            return vec![];
+        }
+        if high - low + 1 >= size {
+            return vec![(0, size - 1)];
+        }
+
+        let (low, high) = (low.rem_euclid(size), high.rem_euclid(size));
+        match low <= high {
+            true => vec![(low, high)],
+            false => vec![(low, size - 1), (0, high)],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NeighbourhoodShape {
+    // Cells within the radius counting diagonal steps as one
+    Moore,
+    // Cells within the radius counting only horizontal and vertical steps
+    VonNeumann,
+}
+
+impl FromStr for NeighbourhoodShape {
+    type Err = String;
+
+    fn from_str(shape_str: &str) -> Result<NeighbourhoodShape, String> {
+        match shape_str {
+            "moore" => Ok(NeighbourhoodShape::Moore),
+            "von-neumann" => Ok(NeighbourhoodShape::VonNeumann),
+            _ => Err(format!(
+                "Invalid neighbourhood '{}', expected moore or von-neumann",
+                shape_str
+            )),
+        }
+    }
+}
+
+// Which cells count as adjacent to a cell. The puzzle uses the eight surrounding cells,
+// a radius one Moore neighbourhood without wrapping
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Neighbourhood {
+    shape: NeighbourhoodShape,
+    radius: usize,
+    // Whether the grid edges join up, so the last column neighbours the first
+    wrap: bool,
+}
+
+impl Default for Neighbourhood {
+    fn default() -> Neighbourhood {
+        Neighbourhood {
+            shape: NeighbourhoodShape::Moore,
+            radius: 1,
+            wrap: false,
+        }
+    }
+}
+
+impl Neighbourhood {
+    fn contains_offset(self: &Self, row_offset: usize, column_offset: usize) -> bool {
+        match self.shape {
+            NeighbourhoodShape::Moore => row_offset.max(column_offset) <= self.radius,
+            NeighbourhoodShape::VonNeumann => row_offset + column_offset <= self.radius,
+        }
+    }
+
+    // Row and column offsets of every neighbour, not including the cell itself
+    fn offsets(self: &Self) -> Vec<(isize, isize)> {
+        let radius = self.radius as isize;
+        (-radius..=radius)
+            .flat_map(|row_offset| {
+                (-radius..=radius).map(move |column_offset| (row_offset, column_offset))
+            })
+            .filter(|(row_offset, column_offset)| {
+                (*row_offset, *column_offset) != (0, 0)
+                    && self.contains_offset(row_offset.unsigned_abs(), column_offset.unsigned_abs())
+            })
+            .collect()
+    }
+
+    // The grid cell a point refers to, wrapping it around the edges if enabled
+    fn resolve<T>(self: &Self, grid: &Grid<T>, point: Point) -> Option<Point> {
+        match (self.wrap, grid.contains(point)) {
+            (_, true) => Some(point),
+            (true, false) if grid.width() > 0 && grid.height() > 0 => Some(grid.wrap(point)),
+            _ => None,
+        }
+    }
+
+    // IDs held by the cells covering the point or any of its neighbours, given a grid of the
+    // ID covering each cell
+    fn ids_touching(self: &Self, cell_ids: &Grid<Option<usize>>, point: Point) -> Vec<usize> {
+        let mut ids: Vec<usize> = self
+            .offsets()
+            .into_iter()
+            .chain([(0, 0)])
+            .filter_map(|(row_offset, column_offset)| {
+                self.resolve(cell_ids, point.offset(row_offset, column_offset))
+            })
+            .filter_map(|neighbour| *cell_ids.get(neighbour).unwrap())
+            .collect();
+        ids.sort();
+        ids.dedup();
+
+        ids
+    }
+
+    // Fits an outline to the grid, clipping it at the edges or wrapping it around them
+    fn fit_outline(self: &Self, outline: Rect, width: usize, height: usize) -> Vec<Rect> {
+        match self.wrap {
+            true => outline.wrap(width, height),
+            false => outline.clip(width, height).into_iter().collect(),
+        }
+    }
+
+    // Whether a cell inside the grid is a neighbour of any cell of the number
+    fn reaches(
+        self: &Self,
+        point: Point,
+        candidate: &PartNumberCandidate,
+        width: usize,
+        height: usize,
+    ) -> bool {
+        let start_row = candidate.start_row as isize;
+        let start_column = candidate.start_column as isize;
+        let end_column = start_column + candidate.length as isize - 1;
+
+        self.contains_offset(
+            self.axis_distance(point.row, start_row, start_row, height),
+            self.axis_distance(point.column, start_column, end_column, width),
+        )
+    }
+
+    // Steps from a position to the nearest position in `low..=high` along one axis
+    fn axis_distance(self: &Self, position: isize, low: isize, high: isize, size: usize) -> usize {
+        if position >= low && position <= high {
+            return 0;
+        }
+
+        match self.wrap {
+            true => {
+                let size = size as isize;
+                (low - position)
+                    .rem_euclid(size)
+                    .min((position - high).rem_euclid(size)) as usize
+            }
+            false => match position < low {
+                true => (low - position) as usize,
+                false => (position - high) as usize,
+            },
+        }
+    }
+}
+
+const DEFAULT_INPUT: &str = "./inputs/day_three.txt";
+
+// Reads a schematic using the symbol and neighbourhood options, printing any warnings
+fn read_raw_schematic(args: &[String], file_path: &str) -> Result<RawSchematic, String> {
+    let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+    let raw = match parse_symbols(args)? {
+        SymbolSet::Explicit(symbols) => RawSchematic::with_symbols(lines, symbols),
+        SymbolSet::Auto => RawSchematic::new(lines),
+    };
+    let raw = raw.with_neighbourhood(Neighbourhood {
+        shape: args::get_value(args, "--neighbourhood")
+            .unwrap_or("moore")
+            .parse::<NeighbourhoodShape>()?,
+        radius: args::get_value(args, "--neighbourhood-radius")
+            .unwrap_or("1")
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid neighbourhood radius: {}", e))?,
+        wrap: args::has_flag(args, "--wrap"),
+    });
+
+    raw.get_warnings()
+        .iter()
+        .for_each(|warning| println!("Warning: {}", warning));
+
+    Ok(raw)
+}
+
+// Reads `--symbols`, rejecting digits and ignored characters since those are always read as
+// part of a number or as empty space
+fn parse_symbols(args: &[String]) -> Result<SymbolSet, String> {
+    let symbols_str = match args::get_value(args, "--symbols") {
+        Some(symbols_str) => symbols_str,
+        None => return Ok(SymbolSet::Auto),
+    };
+    match symbols_str
+        .chars()
+        .find(|char| VALUES.contains(char) || IGNORED.contains(char))
+    {
+        Some(char) => Err(format!(
+            "'{}' is a digit or ignored so can't be a symbol",
+            char
+        )),
+        None => Ok(SymbolSet::Explicit(symbols_str.chars().collect())),
+    }
+}
+
+fn parse_gear_rule(args: &[String]) -> Result<GearRule, String> {
+    Ok(GearRule {
+        symbols: match args::get_value(args, "--gear-symbols") {
+            Some(symbols_str) => symbols_str.chars().collect(),
+            None => Vec::from(GEARS),
+        },
+        adjacency: args::get_value(args, "--gear-count")
+            .unwrap_or("2")
+            .parse::<AdjacencyCount>()?,
+        combine: args::get_value(args, "--gear-combine")
+            .unwrap_or("product")
+            .parse::<Combine>()?,
+    })
+}
+
+// Parses a `row,column` pair
+fn parse_point(point_str: &str) -> Result<Point, String> {
+    let (row_str, column_str) = point_str
+        .split_once(',')
+        .ok_or(format!("Expected row,column but got '{}'", point_str))?;
+    let parse_coordinate = |str: &str| {
+        str.trim()
+            .parse::<isize>()
+            .map_err(|_e| format!("Invalid coordinate '{}'", point_str))
+    };
+
+    Ok(Point::new(
+        parse_coordinate(row_str)?,
+        parse_coordinate(column_str)?,
+    ))
+}
+
+// Parses a `row,column=char` edit, where no character means clearing the cell
+fn parse_edit(edit_str: &str) -> Result<(Point, Option<char>), String> {
+    let (point_str, char_str) = edit_str
+        .split_once('=')
+        .ok_or(format!("Expected row,column=char but got '{}'", edit_str))?;
+    let mut chars = char_str.chars();
+    let char = chars.next();
+    if chars.next().is_some() {
+        return Err(format!("Expected a single character in '{}'", edit_str));
+    }
+
+    Ok((parse_point(point_str)?, char))
+}
+
+// Lists numbers as `value (row, column)`
+fn format_numbers(numbers: &[&PartNumber]) -> String {
+    numbers
+        .iter()
+        .map(|number| {
+            format!(
+                "{} ({}, {})",
+                number.value, number.start_row, number.start_column
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Solves both parts for `--input <path>` (or the default input). Symbols are detected
+// automatically unless `--symbols <chars>` lists them explicitly, e.g. `--symbols '*#+$'`.
+// `--touching <symbol>`, `--lonely-symbols` and `--mixed` query the parsed schematic.
+// Gears can be redefined with `--gear-symbols <chars>`, `--gear-count 2|2+|2-4` and
+// `--gear-combine product|sum|max`, and adjacency with `--neighbourhood moore|von-neumann`,
+// `--neighbourhood-radius <n>` and `--wrap`. `--render` prints the schematic in colour,
+// optionally cropped with `--around <row>,<column>` and `--radius <n>`, and `--svg <path>`
+// writes it out as an image. `--components` groups parts that share symbols into
+// sub-assemblies. `--sparse` skips building the grid and streams the input instead, for
+// huge mostly empty schematics, honouring the symbol and gear options but only the puzzle's
+// adjacency. `--set <row>,<column>=<char>` (repeatable) edits cells one at a time, printing
+// the updated sums after each edit, with an empty character clearing the cell, and
+// `--verify` checks each update against a full parse. `--diff <a> <b>` compares two schematics instead of solving one
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let file_path = args::get_value(args, "--input").unwrap_or(DEFAULT_INPUT);
+    let gear_rule = parse_gear_rule(args)?;
+    if args::has_flag(args, "--sparse") {
+        if let Some(flag) = ["--neighbourhood", "--neighbourhood-radius", "--wrap"]
+            .iter()
+            .find(|flag| args::has_flag(args, flag))
+        {
+            return Err(format!(
+                "{} can't be combined with --sparse, which only supports the puzzle's adjacency",
+                flag
+            ));
+        }
+
+        let symbols = parse_symbols(args)?;
+        let file = File::open(file_path).map_err(|e| e.to_string())?;
+        let totals = sparse::sum_streaming(BufReader::new(file), &symbols, &gear_rule)
+            .map_err(|e| e.to_string())?;
+        println!(
+            "Read {} rows with {} non-empty cells",
+            totals.rows, totals.cells
+        );
+        println!("Sum of part numbers: {}", totals.part_sum);
+        println!("Sum of gear ratios: {}", totals.gear_ratio_sum);
+        return Ok(());
+    }
+
+    if let Some((before_path, after_path)) = args::get_pair(args, "--diff") {
+        let before = Schematic::parse(read_raw_schematic(args, before_path)?);
+        let after = Schematic::parse(read_raw_schematic(args, after_path)?);
+        let report = diff::diff_schematics(
+            &before,
+            &before.gears_for_rule(&gear_rule),
+            &after,
+            &after.gears_for_rule(&gear_rule),
+        );
+        print!("{}", report);
+        return Ok(());
+    }
+
+    let schematic = Schematic::parse(read_raw_schematic(args, file_path)?);
+    let gears = schematic.gears_for_rule(&gear_rule);
+
+    if args::has_flag(args, "--render") {
+        let viewport = match args::get_value(args, "--around") {
+            Some(around_str) => {
+                let radius = args::get_value(args, "--radius")
+                    .unwrap_or("5")
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid radius: {}", e))?;
+                let around = parse_point(around_str)?;
+                let grid = &schematic.raw.grid;
+                let viewport = render::viewport_around(around, radius, grid.width(), grid.height());
+                Some(viewport.ok_or(format!("{} is outside the schematic", around_str))?)
+            }
+            None => None,
+        };
+        print!("{}", render::render(&schematic, &gears, viewport));
+        println!("{}", render::legend());
+    }
+
+    if let Some(svg_path) = args::get_value(args, "--svg") {
+        fs::write(svg_path, svg::to_svg(&schematic, &gears)).map_err(|e| e.to_string())?;
+        println!("Wrote {}", svg_path);
+    }
+
+    let edits = args::get_values(args, "--set");
+    if !edits.is_empty() {
+        let mut incremental =
+            incremental::IncrementalSchematic::new(schematic.raw.clone(), gear_rule.clone());
+        for edit_str in edits {
+            let (point, char) = parse_edit(edit_str)?;
+            let in_grid = match char {
+                Some(char) => incremental.set_cell(point, char),
+                None => incremental.clear_cell(point),
+            };
+            if !in_grid {
+                return Err(format!("{} is outside the schematic", edit_str));
+            }
+            if args::has_flag(args, "--verify") {
+                incremental
+                    .verify()
+                    .map_err(|error| format!("After {}: {}", edit_str, error))?;
+            }
+            println!(
+                "After {}: sum of part numbers {}, sum of gear ratios {}",
+                edit_str,
+                incremental.part_sum(),
+                incremental.gear_ratio_sum()
+            );
+        }
+    }
+
+    if args::has_flag(args, "--components") {
+        print_components(&schematic);
+    }
+
+    if let Some(kind) = args::get_value(args, "--touching").and_then(|kind| kind.chars().next()) {
+        let parts = schematic.parts_touching_kind(kind);
+        println!("Parts touching '{}': {}", kind, format_numbers(&parts));
+    }
+
+    if args::has_flag(args, "--lonely-symbols") {
+        schematic.symbols_without_parts().iter().for_each(|symbol| {
+            println!(
+                "Symbol '{}' at row {} column {} touches no parts",
+                symbol.kind, symbol.position.row, symbol.position.column
+            )
+        });
+    }
+
+    if args::has_flag(args, "--mixed") {
+        let numbers = schematic.numbers_touching_multiple_kinds();
+        println!(
+            "Numbers touching several kinds of symbol: {}",
+            format_numbers(&numbers)
+        );
+    }
+
+    println!(
+        "Sum of part numbers: {}",
+        schematic
+            .parts
+            .iter()
+            .map(|part_number| part_number.value)
+            .sum::<u32>()
+    );
+    println!(
+        "Sum of gear ratios: {}",
+        gears.iter().map(|gear| gear.ratio).sum::<u64>()
+    );
+
+    Ok(())
+}
+
+fn print_components(schematic: &Schematic) {
+    let components = components::connected_components(schematic);
+    components
+        .iter()
+        .enumerate()
+        .for_each(|(component_id, component)| {
+            println!(
+                "Component {}: {} parts, {} symbols, sum {}",
+                component_id + 1,
+                component.part_ids.len(),
+                component.symbol_ids.len(),
+                component.part_sum
+            )
+        });
+
+    println!("Sub-assemblies: {}", components.len());
+    if let Some(largest) = components::largest_component(&components) {
+        let parts: Vec<&PartNumber> = largest
+            .part_ids
+            .iter()
+            .map(|part_id| &schematic.numbers[*part_id])
+            .collect();
+        println!(
+            "Largest: {} parts, sum {}: {}",
+            parts.len(),
+            largest.part_sum,
+            format_numbers(&parts)
+        );
+    }
+}
+
 pub(crate) fn part_one() {
     println!("Day Three, Part One");
-    let lines = inputs::read_inputs_from_file("./inputs/day_three.txt").unwrap();
-    let schematic = RawSchematic::new(lines);
-    let valid_schematics: Vec<PartNumber> = schematic.get_part_numbers();
+    let lines = inputs::read_inputs_from_file(DEFAULT_INPUT).unwrap();
+    let raw = RawSchematic::new(lines);
+    raw.get_warnings()
+        .iter()
+        .for_each(|warning| println!("Warning: {}", warning));
+    let valid_schematics: Vec<PartNumber> = Schematic::parse(raw).parts;
 
     let valid_schematic_values: Vec<u32> = valid_schematics
         .iter()
@@ -346,11 +1097,11 @@ pub(crate) fn part_one() {
 
 pub(crate) fn part_two() {
     println!("Day Three, Part Two");
-    let lines = inputs::read_inputs_from_file("./inputs/day_three.txt").unwrap();
-    let schematic = RawSchematic::new(lines);
-    let valid_gears: Vec<Gear> = schematic.get_gears();
+    let lines = inputs::read_inputs_from_file(DEFAULT_INPUT).unwrap();
+    let schematic = Schematic::parse(RawSchematic::new(lines));
+    let valid_gears: Vec<Gear> = schematic.gears_for_rule(&GearRule::default());
     println!(
         "Sum of gear ratios: {:?}",
-        valid_gears.iter().map(|gear| gear.ratio).sum::<u32>()
+        valid_gears.iter().map(|gear| gear.ratio).sum::<u64>()
     );
 }
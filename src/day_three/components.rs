@@ -0,0 +1,99 @@
+use super::Schematic;
+
+// Disjoint sets with union by size and path halving
+#[derive(Debug, Clone)]
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(count: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..count).collect(),
+            sizes: vec![1; count],
+        }
+    }
+
+    fn find(self: &mut Self, mut node: usize) -> usize {
+        while self.parents[node] != node {
+            self.parents[node] = self.parents[self.parents[node]];
+            node = self.parents[node];
+        }
+
+        node
+    }
+
+    fn union(self: &mut Self, a: usize, b: usize) {
+        let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return;
+        }
+
+        if self.sizes[root_a] < self.sizes[root_b] {
+            std::mem::swap(&mut root_a, &mut root_b);
+        }
+        self.parents[root_b] = root_a;
+        self.sizes[root_a] += self.sizes[root_b];
+    }
+}
+
+// A group of part numbers joined through the symbols they share
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Component {
+    pub(super) part_ids: Vec<usize>,
+    pub(super) symbol_ids: Vec<usize>,
+    pub(super) part_sum: u64,
+}
+
+// Treats numbers and symbols as the nodes of one graph, with an edge wherever a number
+// touches a symbol. Numbers come first, so symbol `i` is node `numbers.len() + i`. Only
+// components holding at least one part are returned, ordered by their first part
+pub(super) fn connected_components(schematic: &Schematic) -> Vec<Component> {
+    let number_count = schematic.numbers.len();
+    let mut sets = UnionFind::new(number_count + schematic.symbols.len());
+    schematic
+        .number_symbols
+        .iter()
+        .enumerate()
+        .for_each(|(number_id, symbol_ids)| {
+            symbol_ids
+                .iter()
+                .for_each(|symbol_id| sets.union(number_id, number_count + symbol_id));
+        });
+
+    // Index of each root's component, assigned as parts are met in order
+    let mut root_components: Vec<Option<usize>> = vec![None; sets.parents.len()];
+    let mut components: Vec<Component> = Vec::new();
+    for number_id in (0..number_count).filter(|number_id| schematic.is_part(*number_id)) {
+        let root = sets.find(number_id);
+        let component_id = *root_components[root].get_or_insert_with(|| {
+            components.push(Component {
+                part_ids: vec![],
+                symbol_ids: vec![],
+                part_sum: 0,
+            });
+            components.len() - 1
+        });
+
+        let component = &mut components[component_id];
+        component.part_ids.push(number_id);
+        component.part_sum += u64::from(schematic.numbers[number_id].value);
+    }
+
+    for symbol_id in 0..schematic.symbols.len() {
+        let root = sets.find(number_count + symbol_id);
+        if let Some(component_id) = root_components[root] {
+            components[component_id].symbol_ids.push(symbol_id);
+        }
+    }
+
+    components
+}
+
+// The component with the most parts, breaking ties by the larger sum
+pub(super) fn largest_component(components: &[Component]) -> Option<&Component> {
+    components
+        .iter()
+        .max_by_key(|component| (component.part_ids.len(), component.part_sum))
+}
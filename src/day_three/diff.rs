@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::grid::Point;
+
+use super::{Gear, PartNumber, Schematic};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) struct CellChange {
+    pub(super) position: Point,
+    // None where the cell is outside that schematic
+    pub(super) before: Option<char>,
+    pub(super) after: Option<char>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct GearChange {
+    pub(super) before: Gear,
+    pub(super) after: Gear,
+}
+
+// Everything that differs between two schematics. Numbers are matched by position and value,
+// so a number whose digits changed shows up as one number disappearing and another appearing
+#[derive(Debug, Clone)]
+pub(super) struct SchematicDiff {
+    pub(super) changed_cells: Vec<CellChange>,
+    // Part numbers in the second schematic that weren't part numbers in the first
+    pub(super) became_valid: Vec<PartNumber>,
+    // Part numbers in the first schematic that aren't part numbers in the second
+    pub(super) became_invalid: Vec<PartNumber>,
+    pub(super) gears_added: Vec<Gear>,
+    pub(super) gears_removed: Vec<Gear>,
+    pub(super) gears_changed: Vec<GearChange>,
+}
+
+// (row, column, length, value)
+type NumberKey = (usize, usize, usize, u32);
+
+fn number_key(number: &PartNumber) -> NumberKey {
+    (
+        number.start_row,
+        number.start_column,
+        number.length,
+        number.value,
+    )
+}
+
+// Every number keyed by position and value, along with whether it is a part number
+fn numbers_by_key(schematic: &Schematic) -> BTreeMap<NumberKey, (&PartNumber, bool)> {
+    schematic
+        .numbers
+        .iter()
+        .enumerate()
+        .map(|(number_id, number)| (number_key(number), (number, schematic.is_part(number_id))))
+        .collect()
+}
+
+fn gears_by_position(gears: &[Gear]) -> BTreeMap<(usize, usize), &Gear> {
+    gears
+        .iter()
+        .map(|gear| ((gear.row, gear.column), gear))
+        .collect()
+}
+
+fn changed_cells(before: &Schematic, after: &Schematic) -> Vec<CellChange> {
+    let (before_grid, after_grid) = (&before.raw.grid, &after.raw.grid);
+    let height = before_grid.height().max(after_grid.height());
+    let width = before_grid.width().max(after_grid.width());
+
+    (0..height as isize)
+        .flat_map(|row| (0..width as isize).map(move |column| Point::new(row, column)))
+        .filter_map(|position| {
+            let change = CellChange {
+                position,
+                before: before_grid.get(position).copied(),
+                after: after_grid.get(position).copied(),
+            };
+            match change.before != change.after {
+                true => Some(change),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+pub(super) fn diff_schematics(
+    before: &Schematic,
+    before_gears: &[Gear],
+    after: &Schematic,
+    after_gears: &[Gear],
+) -> SchematicDiff {
+    let before_numbers = numbers_by_key(before);
+    let after_numbers = numbers_by_key(after);
+    let is_part_in = |numbers: &BTreeMap<NumberKey, (&PartNumber, bool)>, key: &NumberKey| {
+        numbers.get(key).is_some_and(|(_, is_part)| *is_part)
+    };
+
+    let became_valid: Vec<PartNumber> = after_numbers
+        .iter()
+        .filter(|(key, (_, is_part))| *is_part && !is_part_in(&before_numbers, key))
+        .map(|(_, (number, _))| (*number).clone())
+        .collect();
+    let became_invalid: Vec<PartNumber> = before_numbers
+        .iter()
+        .filter(|(key, (_, is_part))| *is_part && !is_part_in(&after_numbers, key))
+        .map(|(_, (number, _))| (*number).clone())
+        .collect();
+
+    let before_gears = gears_by_position(before_gears);
+    let after_gears = gears_by_position(after_gears);
+    let gears_added: Vec<Gear> = after_gears
+        .iter()
+        .filter(|(position, _)| !before_gears.contains_key(position))
+        .map(|(_, gear)| (*gear).clone())
+        .collect();
+    let gears_removed: Vec<Gear> = before_gears
+        .iter()
+        .filter(|(position, _)| !after_gears.contains_key(position))
+        .map(|(_, gear)| (*gear).clone())
+        .collect();
+    let gears_changed: Vec<GearChange> = before_gears
+        .iter()
+        .filter_map(|(position, before_gear)| {
+            let after_gear = after_gears.get(position)?;
+            match before_gear.ratio != after_gear.ratio {
+                true => Some(GearChange {
+                    before: (*before_gear).clone(),
+                    after: (*after_gear).clone(),
+                }),
+                false => None,
+            }
+        })
+        .collect();
+
+    SchematicDiff {
+        changed_cells: changed_cells(before, after),
+        became_valid,
+        became_invalid,
+        gears_added,
+        gears_removed,
+        gears_changed,
+    }
+}
+
+fn format_cell(char: Option<char>) -> String {
+    match char {
+        Some(char) => format!("'{}'", char),
+        None => String::from("nothing"),
+    }
+}
+
+impl fmt::Display for SchematicDiff {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Changed cells: {}", self.changed_cells.len())?;
+        for change in &self.changed_cells {
+            writeln!(
+                f,
+                "  row {} column {}: {} -> {}",
+                change.position.row,
+                change.position.column,
+                format_cell(change.before),
+                format_cell(change.after)
+            )?;
+        }
+
+        for (heading, numbers) in [
+            ("Became part numbers", &self.became_valid),
+            ("No longer part numbers", &self.became_invalid),
+        ] {
+            writeln!(f, "{}: {}", heading, numbers.len())?;
+            for number in numbers {
+                writeln!(
+                    f,
+                    "  {} at row {} column {}",
+                    number.value, number.start_row, number.start_column
+                )?;
+            }
+        }
+
+        for (heading, gears) in [
+            ("Gears added", &self.gears_added),
+            ("Gears removed", &self.gears_removed),
+        ] {
+            writeln!(f, "{}: {}", heading, gears.len())?;
+            for gear in gears {
+                writeln!(
+                    f,
+                    "  row {} column {} with ratio {}",
+                    gear.row, gear.column, gear.ratio
+                )?;
+            }
+        }
+
+        writeln!(
+            f,
+            "Gears with a changed ratio: {}",
+            self.gears_changed.len()
+        )?;
+        for change in &self.gears_changed {
+            writeln!(
+                f,
+                "  row {} column {}: {} -> {}",
+                change.before.row, change.before.column, change.before.ratio, change.after.ratio
+            )?;
+        }
+
+        Ok(())
+    }
+}
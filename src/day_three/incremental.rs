@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::grid::{Grid, Point};
+
+use super::{GearRule, PartNumberCandidate, RawSchematic, Schematic};
+
+#[derive(Debug, Clone)]
+struct TrackedNumber {
+    number: PartNumberCandidate,
+    // How many symbols the number touches, it is a part number while this is above zero
+    symbol_count: usize,
+}
+
+// A schematic that can be edited one cell at a time while keeping the part number and gear
+// ratio sums up to date. Each edit only revisits the numbers on either side of the cell and
+// the gears around them, rather than the whole grid
+#[derive(Debug, Clone)]
+pub(super) struct IncrementalSchematic {
+    raw: RawSchematic,
+    gear_rule: GearRule,
+    numbers: BTreeMap<usize, TrackedNumber>,
+    next_number_id: usize,
+    cell_numbers: Grid<Option<usize>>,
+    // Ratio of each gear currently meeting the rule, keyed by its position
+    gear_ratios: BTreeMap<Point, u64>,
+    part_sum: u64,
+    gear_ratio_sum: u64,
+}
+
+impl IncrementalSchematic {
+    pub(super) fn new(raw: RawSchematic, gear_rule: GearRule) -> IncrementalSchematic {
+        let candidates = raw.find_part_number_candidates();
+        let cell_numbers = Grid::new(raw.grid.width(), raw.grid.height(), None);
+        let mut schematic = IncrementalSchematic {
+            raw,
+            gear_rule,
+            numbers: BTreeMap::new(),
+            next_number_id: 0,
+            cell_numbers,
+            gear_ratios: BTreeMap::new(),
+            part_sum: 0,
+            gear_ratio_sum: 0,
+        };
+
+        candidates
+            .into_iter()
+            .for_each(|candidate| schematic.insert_number(candidate));
+        let gear_points: Vec<Point> = schematic
+            .raw
+            .grid
+            .iter()
+            .map(|(point, _)| point)
+            .filter(|point| schematic.raw.is_gear_at(*point, &schematic.gear_rule))
+            .collect();
+        gear_points
+            .into_iter()
+            .for_each(|point| schematic.refresh_gear(point));
+
+        schematic
+    }
+
+    pub(super) fn part_sum(self: &Self) -> u64 {
+        self.part_sum
+    }
+
+    pub(super) fn gear_ratio_sum(self: &Self) -> u64 {
+        self.gear_ratio_sum
+    }
+
+    // Recomputes both sums from scratch with `Schematic::parse`, as a check on the
+    // incremental updates
+    pub(super) fn verify(self: &Self) -> Result<(), String> {
+        let schematic = Schematic::parse(self.raw.clone());
+        let part_sum: u64 = schematic
+            .parts
+            .iter()
+            .map(|part| u64::from(part.value))
+            .sum();
+        let gear_ratio_sum: u64 = schematic
+            .gears_for_rule(&self.gear_rule)
+            .iter()
+            .map(|gear| gear.ratio)
+            .sum();
+
+        match (part_sum, gear_ratio_sum) == (self.part_sum, self.gear_ratio_sum) {
+            true => Ok(()),
+            false => Err(format!(
+                "Incremental sums {} and {} differ from the full parse's {} and {}",
+                self.part_sum, self.gear_ratio_sum, part_sum, gear_ratio_sum
+            )),
+        }
+    }
+
+    // Replaces the character at the point, returning false if it is outside the grid
+    pub(super) fn set_cell(self: &mut Self, point: Point, char: char) -> bool {
+        let old_char = match self.raw.grid.get(point) {
+            Some(old_char) => *old_char,
+            None => return false,
+        };
+        if old_char == char {
+            return true;
+        }
+
+        // A digit here can join or split the numbers either side, so they are rebuilt
+        let row_neighbours = [point.offset(0, -1), point, point.offset(0, 1)];
+        let removed_ids: BTreeSet<usize> = row_neighbours
+            .iter()
+            .filter_map(|neighbour| self.cell_numbers.get(*neighbour).copied().flatten())
+            .collect();
+        let mut affected_gears: BTreeSet<Point> = BTreeSet::from([point]);
+        for number_id in removed_ids {
+            let tracked = self.remove_number(number_id);
+            affected_gears.extend(self.gears_around(&tracked.number));
+        }
+
+        if self.raw.is_symbol(old_char) {
+            self.adjust_symbol_counts(point, false);
+        }
+        self.raw.grid.set(point, char);
+        if self.raw.is_symbol(char) {
+            self.adjust_symbol_counts(point, true);
+        }
+
+        for neighbour in row_neighbours {
+            if let Some(number) = self.number_at(neighbour) {
+                affected_gears.extend(self.gears_around(&number));
+                self.insert_number(number);
+            }
+        }
+
+        affected_gears
+            .into_iter()
+            .for_each(|gear_point| self.refresh_gear(gear_point));
+
+        true
+    }
+
+    pub(super) fn clear_cell(self: &mut Self, point: Point) -> bool {
+        let ignored = self.raw.ignored[0];
+        self.set_cell(point, ignored)
+    }
+
+    // The whole run of digits through the point, if it holds an untracked digit
+    fn number_at(self: &Self, point: Point) -> Option<PartNumberCandidate> {
+        let is_value = |column: isize| {
+            self.raw
+                .grid
+                .get(Point::new(point.row, column))
+                .is_some_and(|char| self.raw.values.contains(char))
+        };
+        if !is_value(point.column) || self.cell_numbers.get(point)?.is_some() {
+            return None;
+        }
+
+        let mut start = point.column;
+        while is_value(start - 1) {
+            start -= 1;
+        }
+        let mut number = PartNumberCandidate::new(point.row as usize, start as usize, 0, 0);
+        let mut column = start;
+        while is_value(column) {
+            let char = *self.raw.grid.get(Point::new(point.row, column)).unwrap();
+            number = number.append_digit(char as u32 - 0x30);
+            column += 1;
+        }
+
+        Some(number)
+    }
+
+    fn insert_number(self: &mut Self, number: PartNumberCandidate) {
+        let number_id = self.next_number_id;
+        self.next_number_id += 1;
+
+        for point in number_cells(&number) {
+            self.cell_numbers.set(point, Some(number_id));
+        }
+        let symbol_count = self
+            .cells_around(&number)
+            .into_iter()
+            .filter(|point| self.raw.is_symbol_at(point.column, point.row))
+            .count();
+        if symbol_count > 0 {
+            self.part_sum += u64::from(number.value);
+        }
+
+        self.numbers.insert(
+            number_id,
+            TrackedNumber {
+                number,
+                symbol_count,
+            },
+        );
+    }
+
+    fn remove_number(self: &mut Self, number_id: usize) -> TrackedNumber {
+        let tracked = self.numbers.remove(&number_id).unwrap();
+        for point in number_cells(&tracked.number) {
+            self.cell_numbers.set(point, None);
+        }
+        if tracked.symbol_count > 0 {
+            self.part_sum -= u64::from(tracked.number.value);
+        }
+
+        tracked
+    }
+
+    // Counts a symbol appearing or disappearing at the point against the numbers around it
+    fn adjust_symbol_counts(self: &mut Self, point: Point, added: bool) {
+        for number_id in self.numbers_touching(point) {
+            let tracked = self.numbers.get_mut(&number_id).unwrap();
+            let value = u64::from(tracked.number.value);
+            match added {
+                true => {
+                    tracked.symbol_count += 1;
+                    if tracked.symbol_count == 1 {
+                        self.part_sum += value;
+                    }
+                }
+                false => {
+                    tracked.symbol_count -= 1;
+                    if tracked.symbol_count == 0 {
+                        self.part_sum -= value;
+                    }
+                }
+            }
+        }
+    }
+
+    // Replaces the gear's contribution to the ratio sum with its current one
+    fn refresh_gear(self: &mut Self, point: Point) {
+        if let Some(old_ratio) = self.gear_ratios.remove(&point) {
+            self.gear_ratio_sum -= old_ratio;
+        }
+
+        if !self.raw.is_gear_at(point, &self.gear_rule) {
+            return;
+        }
+
+        let number_ids = self.numbers_touching(point);
+        if !self.gear_rule.adjacency.matches(number_ids.len()) {
+            return;
+        }
+        let values: Vec<u64> = number_ids
+            .iter()
+            .map(|number_id| u64::from(self.numbers[number_id].number.value))
+            .collect();
+        let ratio = self.gear_rule.combine.apply(&values);
+        self.gear_ratios.insert(point, ratio);
+        self.gear_ratio_sum += ratio;
+    }
+
+    // IDs of the numbers covering the point or any of its neighbours
+    fn numbers_touching(self: &Self, point: Point) -> Vec<usize> {
+        self.raw
+            .neighbourhood
+            .ids_touching(&self.cell_numbers, point)
+    }
+
+    // Every cell the number's neighbourhood reaches
+    fn cells_around(self: &Self, number: &PartNumberCandidate) -> Vec<Point> {
+        let neighbourhood = &self.raw.neighbourhood;
+        let (width, height) = (self.raw.grid.width(), self.raw.grid.height());
+        let outline = number.get_outline(neighbourhood).unwrap();
+
+        neighbourhood
+            .fit_outline(outline, width, height)
+            .iter()
+            .flat_map(|rect| {
+                (rect.top..(rect.bottom + 1)).flat_map(move |row| {
+                    (rect.left..(rect.right + 1)).map(move |column| Point::new(row, column))
+                })
+            })
+            .filter(|point| neighbourhood.reaches(*point, number, width, height))
+            .collect()
+    }
+
+    fn gears_around(self: &Self, number: &PartNumberCandidate) -> Vec<Point> {
+        self.cells_around(number)
+            .into_iter()
+            .filter(|point| self.raw.is_gear_at(*point, &self.gear_rule))
+            .collect()
+    }
+}
+
+fn number_cells(number: &PartNumberCandidate) -> impl Iterator<Item = Point> {
+    let (row, start_column) = (number.start_row as isize, number.start_column as isize);
+    (0..number.length as isize).map(move |offset| Point::new(row, start_column + offset))
+}
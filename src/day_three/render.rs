@@ -0,0 +1,150 @@
+use crate::ansi;
+use crate::grid::{Grid, Point};
+
+use super::{Gear, Rect, Schematic};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(super) enum CellKind {
+    Empty,
+    Part,
+    // A number that touches no symbol
+    Number,
+    Symbol,
+    Gear,
+    // Anything that is neither a number, a symbol nor ignored
+    Unknown,
+}
+
+impl CellKind {
+    fn colour(self: &Self) -> Option<&'static str> {
+        match self {
+            CellKind::Empty => None,
+            CellKind::Part => Some(ansi::GREEN),
+            CellKind::Number => Some(ansi::RED),
+            CellKind::Symbol => Some(ansi::YELLOW),
+            CellKind::Gear => Some(ansi::MAGENTA),
+            CellKind::Unknown => Some(ansi::CYAN),
+        }
+    }
+}
+
+// The rows and columns within `radius` of the point, clipped to the grid
+pub(super) fn viewport_around(
+    point: Point,
+    radius: usize,
+    width: usize,
+    height: usize,
+) -> Option<Rect> {
+    let radius = radius as isize;
+    Rect {
+        top: point.row - radius,
+        left: point.column - radius,
+        bottom: point.row + radius,
+        right: point.column + radius,
+    }
+    .clip(width, height)
+}
+
+// What each cell holds, with gears taking priority over the symbols they sit on
+pub(super) fn classify(schematic: &Schematic, gears: &[Gear]) -> Grid<CellKind> {
+    let grid = &schematic.raw.grid;
+    let mut kinds = Grid::new(grid.width(), grid.height(), CellKind::Empty);
+
+    grid.iter().for_each(|(point, char)| {
+        if schematic.raw.is_unknown(*char) {
+            kinds.set(point, CellKind::Unknown);
+        }
+    });
+    schematic
+        .numbers
+        .iter()
+        .enumerate()
+        .for_each(|(number_id, number)| {
+            let kind = match schematic.is_part(number_id) {
+                true => CellKind::Part,
+                false => CellKind::Number,
+            };
+            for offset in 0..number.length {
+                let point = Point::new(
+                    number.start_row as isize,
+                    (number.start_column + offset) as isize,
+                );
+                kinds.set(point, kind);
+            }
+        });
+    schematic.symbols.iter().for_each(|symbol| {
+        kinds.set(symbol.position, CellKind::Symbol);
+    });
+    gears.iter().for_each(|gear| {
+        kinds.set(
+            Point::new(gear.row as isize, gear.column as isize),
+            CellKind::Gear,
+        );
+    });
+
+    kinds
+}
+
+// Draws the schematic (or just the viewport) with each kind of cell in its own colour and
+// the row number down the left
+pub(super) fn render(schematic: &Schematic, gears: &[Gear], viewport: Option<Rect>) -> String {
+    let grid = &schematic.raw.grid;
+    let kinds = classify(schematic, gears);
+    let full_grid = Rect {
+        top: 0,
+        left: 0,
+        bottom: grid.height() as isize - 1,
+        right: grid.width() as isize - 1,
+    };
+    let viewport = match viewport.or_else(|| full_grid.clip(grid.width(), grid.height())) {
+        Some(viewport) => viewport,
+        None => return String::new(),
+    };
+    let label_width = viewport.bottom.to_string().len();
+
+    let mut rendered = String::new();
+    for row in viewport.top..(viewport.bottom + 1) {
+        rendered.push_str(&format!("{:>width$} ", row, width = label_width));
+
+        let mut current: Option<&str> = None;
+        for column in viewport.left..(viewport.right + 1) {
+            let point = Point::new(row, column);
+            let colour = kinds.get(point).and_then(|kind| kind.colour());
+            // Only switch colour where it changes, so runs of a number share one escape code
+            if colour != current {
+                if current.is_some() {
+                    rendered.push_str(ansi::RESET);
+                }
+                if let Some(colour) = colour {
+                    rendered.push_str(colour);
+                }
+                current = colour;
+            }
+            rendered.push(*grid.get(point).unwrap());
+        }
+
+        if current.is_some() {
+            rendered.push_str(ansi::RESET);
+        }
+        rendered.push('\n');
+    }
+
+    rendered
+}
+
+// Every kind worth explaining, with its label, for both the terminal and SVG legends
+pub(super) const LEGEND_ITEMS: [(CellKind, &str); 5] = [
+    (CellKind::Part, "part number"),
+    (CellKind::Number, "not a part number"),
+    (CellKind::Symbol, "symbol"),
+    (CellKind::Gear, "gear"),
+    (CellKind::Unknown, "unknown character"),
+];
+
+pub(super) fn legend() -> String {
+    LEGEND_ITEMS
+        .iter()
+        .map(|(kind, name)| ansi::paint(name, kind.colour().unwrap()))
+        .collect::<Vec<String>>()
+        .join("  ")
+}
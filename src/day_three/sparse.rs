@@ -0,0 +1,182 @@
+use std::io::{self, BufRead};
+
+use super::{GearRule, SymbolSet, IGNORED, VALUES};
+
+// Every non-empty cell of a row, kept as column-sorted lists so that empty stretches cost
+// nothing once the line has been scanned. Works on bytes, so the input is expected to be
+// ASCII
+#[derive(Debug, Clone, Default)]
+struct SparseRow {
+    numbers: Vec<SparseNumber>,
+    // Column and character of each symbol
+    symbols: Vec<(usize, char)>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct SparseNumber {
+    start: usize,
+    end: usize,
+    value: u64,
+}
+
+impl SparseRow {
+    // Any byte that isn't a value, ignored or a symbol ends a number without being kept
+    fn parse(line: &[u8], symbols: &SymbolSet) -> SparseRow {
+        let mut row = SparseRow::default();
+        let mut current: Option<SparseNumber> = None;
+
+        for (column, byte) in line.iter().copied().enumerate() {
+            let char = char::from(byte);
+            if VALUES.contains(&char) {
+                let digit = u64::from(byte - b'0');
+                current = Some(match current {
+                    Some(number) => SparseNumber {
+                        end: column,
+                        // Absurdly long digit runs saturate rather than overflow
+                        value: number.value.saturating_mul(10).saturating_add(digit),
+                        ..number
+                    },
+                    None => SparseNumber {
+                        start: column,
+                        end: column,
+                        value: digit,
+                    },
+                });
+                continue;
+            }
+
+            if let Some(number) = current.take() {
+                row.numbers.push(number);
+            }
+            if !IGNORED.contains(&char) && symbols.contains(char, &VALUES, &IGNORED) {
+                row.symbols.push((column, char));
+            }
+        }
+
+        if let Some(number) = current {
+            row.numbers.push(number);
+        }
+
+        row
+    }
+
+    fn cell_count(self: &Self) -> usize {
+        self.symbols.len()
+            + self
+                .numbers
+                .iter()
+                .map(|number| number.end - number.start + 1)
+                .sum::<usize>()
+    }
+
+    // Whether a symbol sits in `low..=high`
+    fn has_symbol_between(self: &Self, low: usize, high: usize) -> bool {
+        let first = self.symbols.partition_point(|(column, _)| *column < low);
+        self.symbols
+            .get(first)
+            .is_some_and(|(column, _)| *column <= high)
+    }
+
+    // Numbers with a digit in `low..=high`
+    fn numbers_between(
+        self: &Self,
+        low: usize,
+        high: usize,
+    ) -> impl Iterator<Item = &SparseNumber> {
+        let first = self.numbers.partition_point(|number| number.end < low);
+        self.numbers[first..]
+            .iter()
+            .take_while(move |number| number.start <= high)
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(super) struct SparseTotals {
+    pub(super) rows: usize,
+    // Cells holding a digit or symbol
+    pub(super) cells: usize,
+    pub(super) part_sum: u64,
+    pub(super) gear_ratio_sum: u64,
+}
+
+impl SparseTotals {
+    // Adds the parts and gears of `middle`, whose neighbouring rows are now both known
+    fn add_row(
+        self: &mut Self,
+        above: &SparseRow,
+        middle: &SparseRow,
+        below: &SparseRow,
+        gear_rule: &GearRule,
+    ) {
+        let window = [above, middle, below];
+
+        // Saturating throughout, since numbers themselves saturate when parsed
+        let part_sum = middle
+            .numbers
+            .iter()
+            .filter(|number| {
+                let low = number.start.saturating_sub(1);
+                window
+                    .iter()
+                    .any(|row| row.has_symbol_between(low, number.end + 1))
+            })
+            .map(|number| number.value)
+            .fold(0, u64::saturating_add);
+        self.part_sum = self.part_sum.saturating_add(part_sum);
+
+        let gear_ratio_sum = middle
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| gear_rule.symbols.contains(symbol))
+            .filter_map(|(column, _)| {
+                let values: Vec<u64> = window
+                    .iter()
+                    .flat_map(|row| row.numbers_between(column.saturating_sub(1), column + 1))
+                    .map(|number| number.value)
+                    .collect();
+                match gear_rule.adjacency.matches(values.len()) {
+                    true => Some(gear_rule.combine.apply(&values)),
+                    false => None,
+                }
+            })
+            .fold(0, u64::saturating_add);
+        self.gear_ratio_sum = self.gear_ratio_sum.saturating_add(gear_ratio_sum);
+    }
+}
+
+// Reads the schematic row by row, holding only three parsed rows at once, and works out
+// the part number and gear ratio sums. Adjacency is always the puzzle's eight surrounding
+// cells, as wider or wrapping neighbourhoods would need more than three rows at a time
+pub(super) fn sum_streaming<R: BufRead>(
+    mut reader: R,
+    symbols: &SymbolSet,
+    gear_rule: &GearRule,
+) -> io::Result<SparseTotals> {
+    let mut totals = SparseTotals::default();
+    let mut above = SparseRow::default();
+    let mut middle: Option<SparseRow> = None;
+    let mut line: Vec<u8> = Vec::new();
+
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        while matches!(line.last(), Some(b'\n' | b'\r')) {
+            line.pop();
+        }
+
+        let below = SparseRow::parse(&line, symbols);
+        totals.rows += 1;
+        totals.cells += below.cell_count();
+        if let Some(middle) = &middle {
+            totals.add_row(&above, middle, &below, gear_rule);
+        }
+        if let Some(previous) = middle.replace(below) {
+            above = previous;
+        }
+        line.clear();
+    }
+
+    if let Some(middle) = middle {
+        totals.add_row(&above, &middle, &SparseRow::default(), gear_rule);
+    }
+
+    Ok(totals)
+}
@@ -0,0 +1,172 @@
+use std::fmt::Write;
+
+use crate::grid::Point;
+
+use super::render::{classify, CellKind, LEGEND_ITEMS};
+use super::{Gear, Rect, Schematic};
+
+// Size of each character cell in pixels
+const CELL_SIZE: usize = 16;
+const LEGEND_HEIGHT: usize = 28;
+// Horizontal space given to each legend entry
+const LEGEND_SPACING: usize = 150;
+
+const PART_COLOUR: &str = "#2e9e44";
+const NUMBER_COLOUR: &str = "#d43d3d";
+const SYMBOL_COLOUR: &str = "#c98a00";
+const GEAR_COLOUR: &str = "#a03cc8";
+const UNKNOWN_COLOUR: &str = "#1d9bb2";
+const EMPTY_COLOUR: &str = "#c8c8c8";
+
+fn kind_colour(kind: CellKind) -> &'static str {
+    match kind {
+        CellKind::Empty => EMPTY_COLOUR,
+        CellKind::Part => PART_COLOUR,
+        CellKind::Number => NUMBER_COLOUR,
+        CellKind::Symbol => SYMBOL_COLOUR,
+        CellKind::Gear => GEAR_COLOUR,
+        CellKind::Unknown => UNKNOWN_COLOUR,
+    }
+}
+
+fn escape(char: char) -> String {
+    match char {
+        '&' => String::from("&amp;"),
+        '<' => String::from("&lt;"),
+        '>' => String::from("&gt;"),
+        '"' => String::from("&quot;"),
+        '\'' => String::from("&apos;"),
+        _ => char.to_string(),
+    }
+}
+
+// Pixel coordinates of the middle of a cell
+fn cell_centre(row: f64, column: f64) -> (f64, f64) {
+    let size = CELL_SIZE as f64;
+    (column * size + size / 2.0, row * size + size / 2.0)
+}
+
+fn write_rect(svg: &mut String, rect: &Rect, colour: &str) {
+    writeln!(
+        svg,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="{}" stroke-width="1.5" rx="3"/>"#,
+        rect.left as usize * CELL_SIZE,
+        rect.top as usize * CELL_SIZE,
+        (rect.right - rect.left + 1) as usize * CELL_SIZE,
+        (rect.bottom - rect.top + 1) as usize * CELL_SIZE,
+        colour
+    )
+    .unwrap();
+}
+
+// Draws every cell as a character, outlines each part number with the area its
+// neighbourhood covers and joins each gear to the numbers it combines
+pub(super) fn to_svg(schematic: &Schematic, gears: &[Gear]) -> String {
+    let grid = &schematic.raw.grid;
+    let kinds = classify(schematic, gears);
+    let width = grid.width() * CELL_SIZE;
+    let height = grid.height() * CELL_SIZE;
+    // Wide enough for the legend even when the grid is narrow
+    let canvas_width = width.max(LEGEND_ITEMS.len() * LEGEND_SPACING);
+    let canvas_height = height + LEGEND_HEIGHT;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="monospace" font-size="{}">"#,
+        canvas_width,
+        canvas_height,
+        canvas_width,
+        canvas_height,
+        CELL_SIZE - 2
+    )
+    .unwrap();
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+    writeln!(svg, "<g>").unwrap();
+    schematic
+        .numbers
+        .iter()
+        .enumerate()
+        .filter(|(number_id, _)| schematic.is_part(*number_id))
+        .filter_map(|(_, number)| number.get_outline(&schematic.raw.neighbourhood))
+        .flat_map(|outline| {
+            schematic
+                .raw
+                .neighbourhood
+                .fit_outline(outline, grid.width(), grid.height())
+        })
+        .for_each(|rect| write_rect(&mut svg, &rect, PART_COLOUR));
+    writeln!(svg, "</g>").unwrap();
+
+    writeln!(svg, "<g>").unwrap();
+    gears.iter().for_each(|gear| {
+        let (x1, y1) = cell_centre(gear.row as f64, gear.column as f64);
+        gear.part_ids.iter().for_each(|part_id| {
+            let part = &schematic.numbers[*part_id];
+            let middle_column = part.start_column as f64 + (part.length as f64 - 1.0) / 2.0;
+            let (x2, y2) = cell_centre(part.start_row as f64, middle_column);
+            writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="2" stroke-opacity="0.7"/>"#,
+                x1, y1, x2, y2, GEAR_COLOUR
+            )
+            .unwrap();
+        });
+    });
+    writeln!(svg, "</g>").unwrap();
+
+    writeln!(
+        svg,
+        r#"<g text-anchor="middle" dominant-baseline="central">"#
+    )
+    .unwrap();
+    grid.iter().for_each(|(point, char)| {
+        let kind = *kinds.get(point).unwrap();
+        let (x, y) = cell_centre(point.row as f64, point.column as f64);
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" fill="{}">{}</text>"#,
+            x,
+            y,
+            kind_colour(kind),
+            escape(*char)
+        )
+        .unwrap();
+    });
+    writeln!(svg, "</g>").unwrap();
+
+    write_legend(&mut svg, Point::new(height as isize, 0));
+    writeln!(svg, "</svg>").unwrap();
+
+    svg
+}
+
+// A row of coloured swatches and labels starting at the given pixel position
+fn write_legend(svg: &mut String, origin: Point) {
+    writeln!(svg, r#"<g dominant-baseline="central">"#).unwrap();
+    LEGEND_ITEMS
+        .iter()
+        .enumerate()
+        .for_each(|(index, (kind, name))| {
+            let x = origin.column as usize + 8 + index * LEGEND_SPACING;
+            let y = origin.row as usize + LEGEND_HEIGHT / 2;
+            writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="10" height="10" fill="{}"/>"#,
+                x,
+                y - 5,
+                kind_colour(*kind)
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" fill="black">{}</text>"#,
+                x + 14,
+                y,
+                name
+            )
+            .unwrap();
+        });
+    writeln!(svg, "</g>").unwrap();
+}
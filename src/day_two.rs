@@ -1,25 +1,209 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Add;
 use std::str::FromStr;
 
-use crate::inputs;
+use crate::{args, inputs};
 
-#[derive(Debug)]
+mod covering;
+mod inference;
+mod query;
+mod simulator;
+mod validation;
+
+// Colours are names made of lowercase ASCII letters, e.g. `red` or `purple`
+fn check_colour(colour: &str) -> Result<(), String> {
+    match !colour.is_empty() && colour.chars().all(|char| char.is_ascii_lowercase()) {
+        true => Ok(()),
+        false => Err(format!("Invalid colour '{}'", colour)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct CubeSet {
     red: u32,
     blue: u32,
     green: u32,
+    // Any colours beyond the classic three, only holding non-zero counts
+    others: BTreeMap<String, u32>,
 }
 
 impl CubeSet {
     fn new(red: u32, green: u32, blue: u32) -> CubeSet {
-        CubeSet { red, green, blue }
+        CubeSet {
+            red,
+            green,
+            blue,
+            others: BTreeMap::new(),
+        }
+    }
+
+    fn get(self: &Self, colour: &str) -> u32 {
+        match colour {
+            "red" => self.red,
+            "green" => self.green,
+            "blue" => self.blue,
+            _ => *self.others.get(colour).unwrap_or(&0),
+        }
+    }
+
+    fn set(self: &mut Self, colour: &str, count: u32) {
+        match colour {
+            "red" => self.red = count,
+            "green" => self.green = count,
+            "blue" => self.blue = count,
+            _ if count == 0 => {
+                self.others.remove(colour);
+            }
+            _ => {
+                self.others.insert(colour.to_string(), count);
+            }
+        }
+    }
+
+    // Fails rather than wrapping when the total no longer fits
+    fn add_cubes(self: &mut Self, colour: &str, count: u32) -> Result<(), String> {
+        let total = self
+            .get(colour)
+            .checked_add(count)
+            .ok_or(format!("Too many {} cubes to add up", colour))?;
+        self.set(colour, total);
+        Ok(())
+    }
+
+    // The classic colours first, followed by any others in name order
+    fn colours(self: &Self) -> Vec<&str> {
+        let mut colours = vec!["red", "green", "blue"];
+        colours.extend(self.others.keys().map(|colour| colour.as_str()));
+        colours
+    }
+
+    // Colour and count pairs in `colours` order
+    fn iter(self: &Self) -> impl Iterator<Item = (&str, u32)> + '_ {
+        [
+            ("red", self.red),
+            ("green", self.green),
+            ("blue", self.blue),
+        ]
+        .into_iter()
+        .chain(
+            self.others
+                .iter()
+                .map(|(colour, count)| (colour.as_str(), *count)),
+        )
+    }
+
+    // Combines the counts of each colour present in either set
+    fn combine(self: &Self, other: &CubeSet, combine_counts: fn(u32, u32) -> u32) -> CubeSet {
+        let mut combined = CubeSet::new(0, 0, 0);
+        self.iter().chain(other.iter()).for_each(|(colour, _)| {
+            combined.set(colour, combine_counts(self.get(colour), other.get(colour)))
+        });
+        combined
+    }
+
+    // The smallest set that both sets fit inside
+    fn join(self: &Self, other: &CubeSet) -> CubeSet {
+        self.combine(other, u32::max)
+    }
+
+    // The largest set that fits inside both sets
+    fn meet(self: &Self, other: &CubeSet) -> CubeSet {
+        self.combine(other, u32::min)
+    }
+
+    fn fits_inside(self: &Self, other: &CubeSet) -> bool {
+        self.iter()
+            .all(|(colour, count)| count <= other.get(colour))
     }
 
+    fn total(self: &Self) -> u32 {
+        self.iter().map(|(_, count)| count).sum()
+    }
+
+    // Parses a bag description such as `red=12,green=13,blue=14`
+    fn from_bag_str(bag_str: &str) -> Result<CubeSet, String> {
+        let mut bag = CubeSet::new(0, 0, 0);
+        let mut seen_colours: Vec<&str> = Vec::new();
+
+        for entry in bag_str.split(',').map(|str| str.trim()) {
+            let (colour, count_str) = match entry.split_once('=') {
+                Some(parts) => parts,
+                None => {
+                    return Err(format!(
+                        "Invalid bag entry '{}', expected colour=count",
+                        entry
+                    ))
+                }
+            };
+
+            let count = match count_str.trim().parse::<u32>() {
+                Ok(count) => count,
+                Err(_e) => return Err(format!("Invalid count in bag entry '{}'", entry)),
+            };
+
+            let colour = colour.trim();
+            check_colour(colour)?;
+            if seen_colours.contains(&colour) {
+                return Err(format!(
+                    "Colour {} appears more than once in the bag",
+                    colour
+                ));
+            }
+            seen_colours.push(colour);
+
+            bag.set(colour, count);
+        }
+
+        Ok(bag)
+    }
+
+    fn to_bag_string(self: &Self) -> String {
+        self.iter()
+            .map(|(colour, count)| format!("{}={}", colour, count))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    // The puzzle's red * green * blue, so a game missing a classic colour has no power.
+    // `others` only holds colours that are present, which multiply in as well
     fn get_power(self: &Self) -> u32 {
-        return self.red * self.blue * self.green;
+        let others_power: u32 = self.others.values().product();
+        return self.red * self.green * self.blue * others_power;
+    }
+}
+
+impl Add for &CubeSet {
+    type Output = CubeSet;
+
+    fn add(self, other: &CubeSet) -> CubeSet {
+        self.combine(other, |count, other_count| count + other_count)
     }
 }
 
-#[derive(Debug)]
+impl Add for CubeSet {
+    type Output = CubeSet;
+
+    fn add(self, other: CubeSet) -> CubeSet {
+        &self + &other
+    }
+}
+
+// Ordered by "fits inside": a set is less than another if no colour has more cubes, so sets
+// with more of one colour and fewer of another are incomparable
+impl PartialOrd for CubeSet {
+    fn partial_cmp(self: &Self, other: &CubeSet) -> Option<Ordering> {
+        match (self.fits_inside(other), other.fits_inside(self)) {
+            (true, true) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (false, false) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Game {
     id: u32,
     rounds: Vec<Round>,
@@ -37,63 +221,120 @@ impl Game {
     }
 
     fn get_minimum_cube_set(self: &Self) -> CubeSet {
-        CubeSet::new(
-            self.rounds
-                .iter()
-                .map(|round| round.result.red)
-                .max()
-                .unwrap(),
-            self.rounds
-                .iter()
-                .map(|round| round.result.green)
-                .max()
-                .unwrap(),
-            self.rounds
-                .iter()
-                .map(|round| round.result.blue)
-                .max()
-                .unwrap(),
-        )
+        self.rounds
+            .iter()
+            .fold(CubeSet::new(0, 0, 0), |minimum, round| {
+                minimum.join(&round.result)
+            })
+    }
+
+    // Every round and colour that drew more cubes than the limit, empty if the game is possible
+    fn explain_possible(self: &Self, limit_cube_set: &CubeSet) -> Vec<LimitViolation> {
+        self.rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(round_index, round)| {
+                round
+                    .result
+                    .iter()
+                    .filter(|(colour, count)| *count > limit_cube_set.get(colour))
+                    .map(|(colour, count)| LimitViolation {
+                        round_index,
+                        colour: colour.to_string(),
+                        count,
+                        limit: limit_cube_set.get(colour),
+                    })
+                    .collect::<Vec<LimitViolation>>()
+            })
+            .collect()
+    }
+
+    // For each colour of the minimum set, the first round that drew that many cubes
+    fn explain_minimum_cube_set(self: &Self) -> Vec<BindingRound> {
+        self.get_minimum_cube_set()
+            .iter()
+            .map(|(colour, count)| BindingRound {
+                colour: colour.to_string(),
+                count,
+                round_index: match count {
+                    0 => None,
+                    _ => self
+                        .rounds
+                        .iter()
+                        .position(|round| round.result.get(colour) == count),
+                },
+            })
+            .collect()
     }
 }
 
-impl FromStr for Game {
-    type Err = String;
+#[derive(Debug, Clone)]
+struct LimitViolation {
+    round_index: usize,
+    colour: String,
+    count: u32,
+    limit: u32,
+}
 
-    fn from_str(game_str: &str) -> Result<Game, String> {
-        // Reference string
-        // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-        let parts: Vec<&str> = game_str.split(":").map(|str| str.trim()).collect();
+impl fmt::Display for LimitViolation {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "round {} has {} {} (limit {})",
+            self.round_index + 1,
+            self.count,
+            self.colour,
+            self.limit
+        )
+    }
+}
+
+// The round setting one colour of a game's minimum set, none if no round drew that colour
+#[derive(Debug, Clone)]
+struct BindingRound {
+    colour: String,
+    count: u32,
+    round_index: Option<usize>,
+}
 
-        if parts.len() != 2 {
-            return Err(String::from("Invalid string for game"));
+impl fmt::Display for BindingRound {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.round_index {
+            Some(index) => write!(f, "{}={} (round {})", self.colour, self.count, index + 1),
+            None => write!(f, "{}={} (no round)", self.colour, self.count),
         }
+    }
+}
 
-        let game_id_str = parts.first().unwrap();
-        let rounds_str = parts.last().unwrap();
+impl FromStr for Game {
+    type Err = String;
 
-        let game_id_chunks: Vec<&str> = game_id_str.split(" ").collect();
-        let last_chunk = match game_id_chunks.last() {
-            Some(chunk) => chunk,
-            None => return Err(String::from("Invalid string for game")),
-        };
+    fn from_str(game_str: &str) -> Result<Game, String> {
+        Game::parse(game_str, DuplicatePolicy::Sum)
+    }
+}
 
-        let game_id = match last_chunk.parse::<u32>() {
-            Ok(val) => val,
-            Err(_e) => return Err(String::from("Invalid string for game")),
-        };
+impl Game {
+    fn parse(game_str: &str, duplicate_policy: DuplicatePolicy) -> Result<Game, String> {
+        let (game_id, rounds_str) = split_game_str(game_str)?;
 
-        let rounds: Vec<Round> = rounds_str
-            .split(";")
-            .map(|str| str.trim())
-            .map(|str| Round::from_str(str).unwrap()) // TODO - figure out how to get rid of this nicely
-            .collect();
+        let rounds: Vec<Round> = split_rounds_str(rounds_str)
+            .map(|str| Round::parse(str, duplicate_policy))
+            .collect::<Result<Vec<Round>, String>>()?;
 
         Ok(Game::new(game_id, rounds))
     }
 }
 
-#[derive(Debug)]
+// Writes the canonical input form, e.g. `Game 1: 4 red, 3 blue; 1 red, 2 green, 6 blue`
+impl fmt::Display for Game {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rounds: Vec<String> = self.rounds.iter().map(|round| round.to_string()).collect();
+        write!(f, "Game {}: {}", self.id, rounds.join("; "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Round {
     result: CubeSet,
 }
@@ -106,64 +347,124 @@ impl Round {
     }
 
     fn round_possible(self: &Self, limit_cube_set: &CubeSet) -> bool {
-        self.result.red <= limit_cube_set.red
-            && self.result.green <= limit_cube_set.green
-            && self.result.blue <= limit_cube_set.blue
+        self.result <= *limit_cube_set
     }
 }
 
+// What to do when a colour appears more than once in a round, e.g. `3 red, 2 red`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuplicatePolicy {
+    Sum,
+    Reject,
+    Max,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(policy_str: &str) -> Result<DuplicatePolicy, String> {
+        match policy_str {
+            "sum" => Ok(DuplicatePolicy::Sum),
+            "reject" => Ok(DuplicatePolicy::Reject),
+            "max" => Ok(DuplicatePolicy::Max),
+            _ => Err(format!(
+                "Invalid duplicate policy '{}', expected sum, reject or max",
+                policy_str
+            )),
+        }
+    }
+}
+
+// Splits a game line into its ID and the text of its rounds. Shared with the validation
+// report so that both agree on which lines are malformed
+fn split_game_str(game_str: &str) -> Result<(u32, &str), String> {
+    // Reference string
+    // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    let parts: Vec<&str> = game_str.split(":").map(|str| str.trim()).collect();
+
+    if parts.len() != 2 {
+        return Err(String::from("Invalid string for game"));
+    }
+
+    let game_id_str = parts.first().unwrap();
+    let rounds_str = parts.last().unwrap();
+
+    let game_id_chunks: Vec<&str> = game_id_str.split(" ").collect();
+    let last_chunk = match game_id_chunks.last() {
+        Some(chunk) => chunk,
+        None => return Err(String::from("Invalid string for game")),
+    };
+
+    match last_chunk.parse::<u32>() {
+        Ok(game_id) => Ok((game_id, rounds_str)),
+        Err(_e) => Err(String::from("Invalid string for game")),
+    }
+}
+
+fn split_rounds_str(rounds_str: &str) -> impl Iterator<Item = &str> {
+    rounds_str.split(";").map(|str| str.trim())
+}
+
+fn split_cubes_str(round_str: &str) -> impl Iterator<Item = &str> {
+    round_str.split(",").map(|str| str.trim())
+}
+
+// Parses a single `<count> <colour>` entry of a round
+fn parse_cube_str(cube_str: &str) -> Result<(u32, &str), String> {
+    // Split parts of the single result with ' '
+    let parts: Vec<&str> = cube_str.split(" ").map(|str| str.trim()).collect();
+    if parts.len() != 2 {
+        return Err(format!("Invalid string for round '{}'", cube_str));
+    }
+
+    // Parse colour count
+    let count = match parts.first().unwrap().parse::<u32>() {
+        Ok(count) => count,
+        Err(_e) => return Err(format!("Invalid count in round '{}'", cube_str)),
+    };
+
+    let colour = parts.last().unwrap().to_owned();
+    check_colour(colour)?;
+
+    Ok((count, colour))
+}
+
 impl FromStr for Round {
     type Err = String;
 
     fn from_str(round_str: &str) -> Result<Round, String> {
-        let cube_strings: Vec<&str> = round_str.split(",").map(|str| str.trim()).collect();
+        Round::parse(round_str, DuplicatePolicy::Sum)
+    }
+}
 
-        if cube_strings.len() > 3 {
-            return Err(String::from("Invalid string for round"));
-        }
+impl Round {
+    fn parse(round_str: &str, duplicate_policy: DuplicatePolicy) -> Result<Round, String> {
+        let cube_strings: Vec<&str> = split_cubes_str(round_str).collect();
 
         let mut round: Round = Round::new(0, 0, 0);
+        let mut seen_colours: Vec<&str> = Vec::new();
 
         let result = cube_strings.iter().try_for_each(|cube_result_str| {
-            // Split parts of the single result with ' '
-            let parts: Vec<&str> = cube_result_str.split(" ").map(|str| str.trim()).collect();
-            if parts.len() != 2 {
-                return Err(String::from("Invalid string for round"));
-            }
-
-            // Parse colour count
-            let count = match parts.first().unwrap().parse::<u32>() {
-                Ok(count) => count,
-                Err(_e) => return Err(String::from("Invalid string for round")),
-            };
-
-            let color = parts.last().unwrap().to_owned();
+            let (count, color) = parse_cube_str(cube_result_str)?;
 
             // Add colour to existing round data
-            match color {
-                "red" => {
-                    round = Round::new(
-                        round.result.red + count,
-                        round.result.green,
-                        round.result.blue,
-                    )
+            if seen_colours.contains(&color) {
+                match duplicate_policy {
+                    DuplicatePolicy::Sum => round.result.add_cubes(color, count)?,
+                    DuplicatePolicy::Reject => {
+                        return Err(format!(
+                            "Invalid string for round, colour {} appears more than once",
+                            color
+                        ))
+                    }
+                    DuplicatePolicy::Max => {
+                        round.result.set(color, count.max(round.result.get(color)))
+                    }
                 }
-                "green" => {
-                    round = Round::new(
-                        round.result.red,
-                        round.result.green + count,
-                        round.result.blue,
-                    )
-                }
-                "blue" => {
-                    round = Round::new(
-                        round.result.red,
-                        round.result.green,
-                        round.result.blue + count,
-                    )
-                }
-                _ => return Err(String::from("Invalid string for round, colour not found")),
-            };
+            } else {
+                round.result.add_cubes(color, count)?;
+                seen_colours.push(color);
+            }
 
             Ok(())
         });
@@ -175,9 +476,450 @@ impl FromStr for Round {
     }
 }
 
+// Writes the non-zero colours in `CubeSet::colours` order. A round with no cubes is written
+// as `0 red` since an empty round wouldn't parse
+impl fmt::Display for Round {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cubes: Vec<String> = self
+            .result
+            .iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(colour, count)| format!("{} {}", count, colour))
+            .collect();
+
+        match cubes.is_empty() {
+            true => write!(f, "0 red"),
+            false => write!(f, "{}", cubes.join(", ")),
+        }
+    }
+}
+
+// The number of ways to pick one index below each size, or none if that's more than `limit`.
+// Shared by the bag searches to check their size before calling `for_each_combination`
+fn combination_count(sizes: &[usize], limit: usize) -> Option<usize> {
+    sizes
+        .iter()
+        .try_fold(1usize, |acc, size| acc.checked_mul(*size))
+        .filter(|count| *count <= limit)
+}
+
+// Visits every way to pick one index below each size, stepping the indices like an odometer
+// with the first turning fastest
+fn for_each_combination(sizes: &[usize], mut visit: impl FnMut(&[usize])) {
+    if sizes.contains(&0) {
+        return;
+    }
+
+    let mut indices: Vec<usize> = vec![0; sizes.len()];
+    loop {
+        visit(&indices);
+
+        let mut position = 0;
+        while position < indices.len() && indices[position] == sizes[position] - 1 {
+            indices[position] = 0;
+            position += 1;
+        }
+        if position == indices.len() {
+            break;
+        }
+        indices[position] += 1;
+    }
+}
+
+const DEFAULT_INPUT: &str = "./inputs/day_two.txt";
+
+fn default_bag() -> CubeSet {
+    CubeSet::new(12, 13, 14)
+}
+
+// Reads bags from a file with one bag per line, skipping blank lines and `#` comments
+fn read_bags_from_file(file_path: &str) -> Result<Vec<CubeSet>, String> {
+    let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+    lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(CubeSet::from_bag_str)
+        .collect()
+}
+
+fn read_games_from_file(
+    file_path: &str,
+    duplicate_policy: DuplicatePolicy,
+) -> Result<Vec<Game>, String> {
+    let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+    lines
+        .iter()
+        .enumerate()
+        .map(|(line_index, line)| {
+            Game::parse(line, duplicate_policy)
+                .map_err(|e| format!("Line {}: {}", line_index + 1, e))
+        })
+        .collect()
+}
+
+fn get_possible_game_ids(games: &[Game], bag: &CubeSet) -> Vec<u32> {
+    games
+        .iter()
+        .filter(|game| game.game_possible(bag))
+        .map(|game| game.id)
+        .collect()
+}
+
+// Re-emits each game line in canonical form, checking that the canonical form parses back
+// to the same game
+fn normalise_lines(
+    lines: &[String],
+    duplicate_policy: DuplicatePolicy,
+) -> Result<Vec<String>, String> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(line_index, line)| {
+            let game = Game::parse(line, duplicate_policy)
+                .map_err(|e| format!("Line {}: {}", line_index + 1, e))?;
+            let normalised = game.to_string();
+
+            match Game::parse(&normalised, duplicate_policy) {
+                Ok(reparsed) if reparsed == game => Ok(normalised),
+                _ => Err(format!(
+                    "Line {} does not round-trip: {}",
+                    line_index + 1,
+                    normalised
+                )),
+            }
+        })
+        .collect()
+}
+
+// Parses a rounds per game option, either a single count like `6` or a range like `3-6`
+fn parse_round_range(rounds_str: &str) -> Result<(u32, u32), String> {
+    let parse_count = |count_str: &str| {
+        count_str
+            .trim()
+            .parse::<u32>()
+            .map_err(|_e| format!("Invalid rounds per game '{}'", rounds_str))
+    };
+
+    match rounds_str.split_once('-') {
+        Some((min_str, max_str)) => Ok((parse_count(min_str)?, parse_count(max_str)?)),
+        None => {
+            let count = parse_count(rounds_str)?;
+            Ok((count, count))
+        }
+    }
+}
+
+// Prints games drawn from the bag in the puzzle input format. Takes `--games <n>`,
+// `--rounds <n>` or `--rounds <min>-<max>` and `--seed <n>`
+fn print_simulated_games(bag: &CubeSet, args: &[String]) -> Result<(), String> {
+    let game_count = match args::get_value(args, "--games") {
+        Some(count_str) => count_str
+            .parse::<u32>()
+            .map_err(|_e| format!("Invalid game count '{}'", count_str))?,
+        None => 100,
+    };
+    let (min_rounds, max_rounds) =
+        parse_round_range(args::get_value(args, "--rounds").unwrap_or("1-6"))?;
+    let seed = match args::get_value(args, "--seed") {
+        Some(seed_str) => seed_str
+            .parse::<u64>()
+            .map_err(|_e| format!("Invalid seed '{}'", seed_str))?,
+        None => 0,
+    };
+
+    let mut simulator = simulator::GameSimulator::new(bag.clone(), min_rounds, max_rounds, seed)?;
+    simulator
+        .simulate_games(game_count)
+        .iter()
+        .for_each(|line| println!("{}", line));
+
+    Ok(())
+}
+
+// Names the rounds that make each game impossible under each bag, and the rounds that set
+// each colour of every game's minimum set
+fn print_explanations(games: &[Game], bags: &[CubeSet]) {
+    bags.iter().for_each(|bag| {
+        println!("Bag {}", bag.to_bag_string());
+        games.iter().for_each(|game| {
+            let violations = game.explain_possible(bag);
+            if !violations.is_empty() {
+                let reasons: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                println!("  Game {} is impossible: {}", game.id, reasons.join("; "));
+            }
+        });
+    });
+
+    println!("Minimum sets");
+    games.iter().for_each(|game| {
+        let binding_rounds: Vec<String> = game
+            .explain_minimum_cube_set()
+            .iter()
+            .map(|binding| binding.to_string())
+            .collect();
+        println!("  Game {}: {}", game.id, binding_rounds.join(", "));
+    });
+}
+
+fn print_covering_bag(covering: &covering::CoveringBag) {
+    println!(
+        "Bag {} (total {}, power {}) makes {} games possible: {:?}",
+        covering.bag.to_bag_string(),
+        covering.bag.total(),
+        covering.bag.get_power(),
+        covering.game_ids.len(),
+        covering.game_ids
+    );
+}
+
+// Answers `--cover <id,id,...>` with the smallest bag making those games possible,
+// `--pareto` with the bags trading size against games possible (ranked by
+// `--objective total|power`) and `--max-games-total <n>` with the most games a bag of that
+// many cubes can make possible
+fn print_covering_bags(games: &[Game], args: &[String]) -> Result<(), String> {
+    let objective = args::get_value(args, "--objective")
+        .unwrap_or("total")
+        .parse::<covering::Objective>()?;
+
+    if let Some(ids_str) = args::get_value(args, "--cover") {
+        let game_ids = ids_str
+            .split(',')
+            .map(|id_str| {
+                id_str
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_e| format!("Invalid game ID '{}'", id_str))
+            })
+            .collect::<Result<Vec<u32>, String>>()?;
+        let bag = covering::minimal_covering_bag(games, &game_ids)?;
+        println!("Smallest bag for games {:?}:", game_ids);
+        print_covering_bag(&covering::CoveringBag::new(games, bag));
+    }
+
+    if args::has_flag(args, "--pareto") {
+        println!("Pareto-minimal bags ({:?}):", objective);
+        covering::pareto_minimal_bags(games, objective)?
+            .iter()
+            .for_each(print_covering_bag);
+    }
+
+    if let Some(total_str) = args::get_value(args, "--max-games-total") {
+        let total = total_str
+            .parse::<u32>()
+            .map_err(|_e| format!("Invalid total '{}'", total_str))?;
+        println!("Most games possible with {} cubes:", total);
+        print_covering_bag(&covering::max_games_with_total(games, total)?);
+    }
+
+    Ok(())
+}
+
+// Estimates the bag the games were drawn from, treating the given bags as hypotheses.
+// Takes `--prior uniform|poisson=<mean>` and `--max-count <n>` to bound the search
+fn print_bag_inference(games: &[Game], bags: &[CubeSet], args: &[String]) -> Result<(), String> {
+    let prior = args::get_value(args, "--prior")
+        .unwrap_or("uniform")
+        .parse::<inference::Prior>()?;
+
+    let observed_max = games
+        .iter()
+        .map(|game| game.get_minimum_cube_set())
+        .flat_map(|minimum| minimum.iter().map(|(_, count)| count).collect::<Vec<u32>>())
+        .max()
+        .unwrap_or(0);
+    let max_count = match args::get_value(args, "--max-count") {
+        Some(max_str) => max_str
+            .parse::<u32>()
+            .map_err(|_e| format!("Invalid max count '{}'", max_str))?,
+        None => observed_max.saturating_mul(2),
+    };
+
+    let bag_colours: Vec<&str> = bags.iter().flat_map(|bag| bag.colours()).collect();
+    let bag_inference = inference::BagInference::new(games, &bag_colours, prior, max_count)?;
+
+    println!(
+        "Searched {} bags over {} up to {} cubes each ({:?} prior)",
+        bag_inference.candidate_count(),
+        bag_inference.colours().join(", "),
+        max_count,
+        prior
+    );
+    let maximum_likelihood_bag = bag_inference.maximum_likelihood_bag();
+    println!(
+        "Maximum likelihood bag: {}",
+        maximum_likelihood_bag.to_bag_string()
+    );
+    if maximum_likelihood_bag
+        .iter()
+        .any(|(_, count)| count == max_count)
+    {
+        println!("  (estimate reaches the search limit, try a larger --max-count)");
+    }
+    let (most_probable_bag, most_probable_bag_probability) = bag_inference.most_probable_bag();
+    println!(
+        "Most probable bag: {} (p = {:.4})",
+        most_probable_bag.to_bag_string(),
+        most_probable_bag_probability
+    );
+    println!("Most probable count of each colour:");
+    bag_inference
+        .most_probable_counts()
+        .iter()
+        .for_each(|(colour, count, probability)| {
+            println!("  {}: {} (p = {:.4})", colour, count, probability)
+        });
+
+    bags.iter().for_each(|bag| {
+        println!("Hypothesis {}", bag.to_bag_string());
+        println!(
+            "  Possible for every game: {}",
+            games.iter().all(|game| game.game_possible(bag))
+        );
+        println!(
+            "  Posterior probability of exactly this bag: {:.4}",
+            bag_inference.posterior_probability(bag)
+        );
+        println!(
+            "  Probability the true bag fits within it: {:.4}",
+            bag_inference.probability_consistent(bag)
+        );
+    });
+
+    Ok(())
+}
+
+// Evaluates each bag given with `--bag red=12,green=13,blue=14` (repeatable) or listed in
+// `--bag-file <path>`, falling back to the puzzle's 12/13/14 bag when none are given.
+// `--duplicates sum|reject|max` sets how a colour repeated within a round is handled and
+// `--validate` lists suspicious rounds rather than solving
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let mut bags: Vec<CubeSet> = args::get_values(args, "--bag")
+        .into_iter()
+        .map(CubeSet::from_bag_str)
+        .collect::<Result<Vec<CubeSet>, String>>()?;
+    if let Some(bag_file_path) = args::get_value(args, "--bag-file") {
+        bags.extend(read_bags_from_file(bag_file_path)?);
+    }
+    if bags.is_empty() {
+        bags.push(default_bag());
+    }
+
+    if args::has_flag(args, "--simulate") {
+        return print_simulated_games(&bags[0], args);
+    }
+
+    let file_path = args::get_value(args, "--input").unwrap_or(DEFAULT_INPUT);
+    let duplicate_policy = args::get_value(args, "--duplicates")
+        .unwrap_or("sum")
+        .parse::<DuplicatePolicy>()?;
+
+    if args::has_flag(args, "--validate") {
+        let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+        let issues = validation::validate_lines(&lines);
+        issues.iter().for_each(|issue| println!("{}", issue));
+        println!("{} issues found in {} lines", issues.len(), lines.len());
+        return Ok(());
+    }
+
+    if args::has_flag(args, "--normalise") {
+        let lines = inputs::read_inputs_from_file(file_path).map_err(|e| e.to_string())?;
+        normalise_lines(&lines, duplicate_policy)?
+            .iter()
+            .for_each(|line| println!("{}", line));
+        return Ok(());
+    }
+
+    let games = read_games_from_file(file_path, duplicate_policy)?;
+
+    if args::get_value(args, "--cover").is_some()
+        || args::has_flag(args, "--pareto")
+        || args::get_value(args, "--max-games-total").is_some()
+    {
+        return print_covering_bags(&games, args);
+    }
+
+    if args::has_flag(args, "--infer") {
+        return print_bag_inference(&games, &bags, args);
+    }
+
+    if args::has_flag(args, "--explain") {
+        print_explanations(&games, &bags);
+        return Ok(());
+    }
+
+    bags.iter().for_each(|bag| {
+        let possible_game_ids = get_possible_game_ids(&games, bag);
+        println!("Bag {}", bag.to_bag_string());
+        println!("  Possible games: {:?}", possible_game_ids);
+        println!(
+            "  Sum of possible game IDs: {}",
+            possible_game_ids.iter().sum::<u32>()
+        );
+    });
+
+    let power_sum: u32 = games
+        .iter()
+        .map(|game| game.get_minimum_cube_set().get_power())
+        .sum();
+    println!("Sum of game minimum possible cube powers: {}", power_sum);
+
+    Ok(())
+}
+
+// Runs a query such as `max(red) > 10 and rounds >= 4` over every game, printing the IDs
+// of matching games for conditions or each game's value for numeric queries
+pub(crate) fn run_query(args: &[String]) -> Result<(), String> {
+    let query_str = match args.first() {
+        Some(query_str) => query_str,
+        None => return Err(String::from("Expected a query, e.g. \"max(red) > 10\"")),
+    };
+    let query = query::Query::parse(query_str)?;
+
+    let file_path = args::get_value(args, "--input").unwrap_or(DEFAULT_INPUT);
+    let games = read_games_from_file(file_path, DuplicatePolicy::Sum)?;
+
+    let values = games
+        .iter()
+        .map(|game| query.evaluate(game).map(|value| (game.id, value)))
+        .collect::<Result<Vec<(u32, query::Value)>, String>>()?;
+
+    match values.first() {
+        Some((_, query::Value::Bool(_))) => {
+            let matching_ids: Vec<u32> = values
+                .iter()
+                .filter(|(_, value)| *value == query::Value::Bool(true))
+                .map(|(id, _)| *id)
+                .collect();
+            println!("Matching games: {:?}", matching_ids);
+            println!(
+                "{} of {} games match, ID sum {}",
+                matching_ids.len(),
+                games.len(),
+                matching_ids.iter().sum::<u32>()
+            );
+        }
+        _ => {
+            values
+                .iter()
+                .for_each(|(id, value)| println!("Game {}: {}", id, value));
+            let total: i64 = values
+                .iter()
+                .map(|(_, value)| match value {
+                    query::Value::Number(number) => *number,
+                    query::Value::Bool(_) => 0,
+                })
+                .sum();
+            println!("Total: {}", total);
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn part_one() {
     println!("Day Two, Part One");
-    let lines = inputs::read_inputs_from_file("./inputs/day_two.txt").unwrap();
+    let lines = inputs::read_inputs_from_file(DEFAULT_INPUT).unwrap();
 
     let games: Vec<Game> = lines
         .iter()
@@ -186,7 +928,7 @@ pub(crate) fn part_one() {
 
     let valid_games: Vec<&Game> = games
         .iter()
-        .filter(|game| game.game_possible(&CubeSet::new(12, 13, 14)))
+        .filter(|game| game.game_possible(&default_bag()))
         .collect();
 
     let valid_game_id_sum = valid_games
@@ -200,7 +942,7 @@ pub(crate) fn part_one() {
 
 pub(crate) fn part_two() {
     println!("Day Two, Part Two");
-    let lines = inputs::read_inputs_from_file("./inputs/day_two.txt").unwrap();
+    let lines = inputs::read_inputs_from_file(DEFAULT_INPUT).unwrap();
 
     let games: Vec<Game> = lines
         .iter()
@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use super::{combination_count, for_each_combination, CubeSet, Game};
+
+// Each threshold combination builds a bag and compares it with every game's minimum set
+const MAX_CANDIDATES: usize = 5_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Objective {
+    TotalCubes,
+    Power,
+}
+
+impl Objective {
+    fn score(self: &Self, bag: &CubeSet) -> u64 {
+        match self {
+            Objective::TotalCubes => u64::from(bag.total()),
+            Objective::Power => u64::from(bag.get_power()),
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(objective_str: &str) -> Result<Objective, String> {
+        match objective_str {
+            "total" => Ok(Objective::TotalCubes),
+            "power" => Ok(Objective::Power),
+            _ => Err(format!(
+                "Invalid objective '{}', expected total or power",
+                objective_str
+            )),
+        }
+    }
+}
+
+// A bag along with every game it makes possible
+#[derive(Debug, Clone)]
+pub(super) struct CoveringBag {
+    pub(super) bag: CubeSet,
+    pub(super) game_ids: Vec<u32>,
+}
+
+impl CoveringBag {
+    pub(super) fn new(games: &[Game], bag: CubeSet) -> CoveringBag {
+        let game_ids = games
+            .iter()
+            .filter(|game| game.game_possible(&bag))
+            .map(|game| game.id)
+            .collect();
+        CoveringBag { bag, game_ids }
+    }
+}
+
+// The smallest bag making every chosen game possible. The join of their minimum sets fits
+// inside any other such bag, so it is smallest by total cubes and by power alike
+pub(super) fn minimal_covering_bag(games: &[Game], game_ids: &[u32]) -> Result<CubeSet, String> {
+    game_ids.iter().try_fold(CubeSet::new(0, 0, 0), |bag, id| {
+        match games.iter().find(|game| game.id == *id) {
+            Some(game) => Ok(bag.join(&game.get_minimum_cube_set())),
+            None => Err(format!("No game with ID {}", id)),
+        }
+    })
+}
+
+// Every distinct bag that is the join of the minimum sets of the games it makes possible.
+// Any other bag can be shrunk to one of these without losing a game
+fn tight_bags(games: &[Game]) -> Result<Vec<CoveringBag>, String> {
+    let minimum_sets: Vec<CubeSet> = games
+        .iter()
+        .map(|game| game.get_minimum_cube_set())
+        .collect();
+
+    // The counts worth trying for each colour are the ones some game needs, or none at all
+    let mut thresholds: BTreeMap<String, BTreeSet<u32>> = BTreeMap::new();
+    minimum_sets.iter().for_each(|minimum| {
+        minimum.iter().for_each(|(colour, count)| {
+            let colour_thresholds = thresholds.entry(colour.to_string()).or_default();
+            colour_thresholds.insert(0);
+            colour_thresholds.insert(count);
+        })
+    });
+    let colours: Vec<&String> = thresholds.keys().collect();
+    let values: Vec<Vec<u32>> = thresholds
+        .values()
+        .map(|set| set.iter().copied().collect())
+        .collect();
+
+    let sizes: Vec<usize> = values
+        .iter()
+        .map(|colour_values| colour_values.len())
+        .collect();
+    combination_count(&sizes, MAX_CANDIDATES)
+        .ok_or_else(|| String::from("Too many candidate bags to enumerate"))?;
+
+    let mut seen: BTreeSet<String> = BTreeSet::new();
+    let mut bags: Vec<CoveringBag> = Vec::new();
+    for_each_combination(&sizes, |indices| {
+        let mut candidate = CubeSet::new(0, 0, 0);
+        colours.iter().zip(indices.iter()).enumerate().for_each(
+            |(colour_index, (colour, value_index))| {
+                candidate.set(colour, values[colour_index][*value_index])
+            },
+        );
+
+        let tight = minimum_sets
+            .iter()
+            .filter(|minimum| **minimum <= candidate)
+            .fold(CubeSet::new(0, 0, 0), |bag, minimum| bag.join(minimum));
+        if seen.insert(tight.to_bag_string()) {
+            bags.push(CoveringBag::new(games, tight));
+        }
+    });
+
+    Ok(bags)
+}
+
+// Bags where making another game possible would cost a larger objective, ordered from the
+// smallest bag to the one covering every game
+pub(super) fn pareto_minimal_bags(
+    games: &[Game],
+    objective: Objective,
+) -> Result<Vec<CoveringBag>, String> {
+    let mut bags = tight_bags(games)?;
+    bags.sort_by(|a, b| {
+        objective
+            .score(&a.bag)
+            .cmp(&objective.score(&b.bag))
+            .then(b.game_ids.len().cmp(&a.game_ids.len()))
+    });
+
+    let mut frontier: Vec<CoveringBag> = Vec::new();
+    bags.into_iter().for_each(|covering| {
+        let best_so_far = frontier.last().map_or(0, |last| last.game_ids.len());
+        if frontier.is_empty() || covering.game_ids.len() > best_so_far {
+            frontier.push(covering);
+        }
+    });
+
+    Ok(frontier)
+}
+
+// The bag of at most `total` cubes making the most games possible. Spare cubes can go in
+// any colour without changing which games are possible
+pub(super) fn max_games_with_total(games: &[Game], total: u32) -> Result<CoveringBag, String> {
+    pareto_minimal_bags(games, Objective::TotalCubes)?
+        .into_iter()
+        .rev()
+        .find(|covering| covering.bag.total() <= total)
+        .ok_or_else(|| String::from("No bag fits within the given total"))
+}
@@ -0,0 +1,369 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use super::{combination_count, for_each_combination, CubeSet, Game};
+
+// Every candidate bag's counts and posterior are kept in memory for the summaries
+const MAX_CANDIDATES: usize = 20_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Prior {
+    // Every count between the observed maximum and the search limit is equally likely
+    Uniform,
+    // Each colour's count is independently Poisson distributed around the given mean
+    Poisson(f64),
+}
+
+impl FromStr for Prior {
+    type Err = String;
+
+    fn from_str(prior_str: &str) -> Result<Prior, String> {
+        // Reference strings
+        // uniform
+        // poisson=12.5
+        match prior_str.split_once('=') {
+            None if prior_str == "uniform" => Ok(Prior::Uniform),
+            Some(("poisson", mean_str)) => match mean_str.parse::<f64>() {
+                Ok(mean) if mean > 0.0 => Ok(Prior::Poisson(mean)),
+                _ => Err(format!("Invalid mean for poisson prior '{}'", mean_str)),
+            },
+            _ => Err(format!(
+                "Invalid prior '{}', expected uniform or poisson=<mean>",
+                prior_str
+            )),
+        }
+    }
+}
+
+// How often each count was drawn, so likelihoods can be computed per distinct count rather
+// than per round
+#[derive(Debug, Clone)]
+struct Histogram {
+    counts: BTreeMap<u32, u32>,
+}
+
+impl Histogram {
+    fn new() -> Histogram {
+        Histogram {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    fn add(self: &mut Self, value: u32) {
+        *self.counts.entry(value).or_insert(0) += 1;
+    }
+
+    fn max(self: &Self) -> u32 {
+        *self.counts.keys().last().unwrap_or(&0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LogFactorials {
+    values: Vec<f64>,
+}
+
+impl LogFactorials {
+    fn new(limit: u32) -> LogFactorials {
+        let mut values = vec![0.0];
+        for i in 1..=limit {
+            values.push(values.last().unwrap() + f64::from(i).ln());
+        }
+        LogFactorials { values }
+    }
+
+    fn get(self: &Self, n: u32) -> f64 {
+        self.values[n as usize]
+    }
+
+    // ln(n choose k), negative infinity when k > n
+    fn choose(self: &Self, n: u32, k: u32) -> f64 {
+        if k > n {
+            return f64::NEG_INFINITY;
+        }
+        self.get(n) - self.get(k) - self.get(n - k)
+    }
+}
+
+// Posterior over every bag from the smallest feasible one up to `max_count` cubes of each
+// colour. Each round is modelled as a draw without replacement from a full bag, so the
+// likelihood of a round is multivariate hypergeometric
+#[derive(Debug, Clone)]
+pub(super) struct BagInference {
+    colours: Vec<String>,
+    // Every candidate's counts back to back, one per colour in `colours` order
+    candidates: Vec<u32>,
+    maximum_likelihood_index: usize,
+    posteriors: Vec<f64>,
+}
+
+impl BagInference {
+    pub(super) fn new(
+        games: &[Game],
+        extra_colours: &[&str],
+        prior: Prior,
+        max_count: u32,
+    ) -> Result<BagInference, String> {
+        // Classic colours first, then any others in name order to match `CubeSet::colours`
+        let mut colour_names: BTreeSet<&str> = extra_colours.iter().copied().collect();
+        games
+            .iter()
+            .flat_map(|game| game.rounds.iter())
+            .for_each(|round| colour_names.extend(round.result.colours()));
+        let classic_colours = ["red", "green", "blue"];
+        let colours: Vec<String> = classic_colours
+            .iter()
+            .copied()
+            .chain(
+                colour_names
+                    .into_iter()
+                    .filter(|colour| !classic_colours.contains(colour)),
+            )
+            .map(String::from)
+            .collect();
+
+        let too_many_candidates = || {
+            format!(
+                "Too many candidate bags for {} colours up to {} cubes each",
+                colours.len(),
+                max_count
+            )
+        };
+
+        // Sufficient statistics: the counts drawn of each colour and the size of each draw.
+        // A draw too large to count could only come from a bag too large to search
+        let mut colour_histograms: Vec<Histogram> = vec![Histogram::new(); colours.len()];
+        let mut draw_size_histogram = Histogram::new();
+        games
+            .iter()
+            .flat_map(|game| game.rounds.iter())
+            .try_for_each(|round| {
+                colours.iter().enumerate().for_each(|(index, colour)| {
+                    colour_histograms[index].add(round.result.get(colour))
+                });
+                let draw_size = colours
+                    .iter()
+                    .try_fold(0u32, |acc, colour| {
+                        acc.checked_add(round.result.get(colour))
+                    })
+                    .ok_or_else(too_many_candidates)?;
+                draw_size_histogram.add(draw_size);
+                Ok::<(), String>(())
+            })?;
+
+        let minimums: Vec<u32> = colour_histograms.iter().map(|hist| hist.max()).collect();
+        if let Some(index) = minimums.iter().position(|min| *min > max_count) {
+            return Err(format!(
+                "Search limit {} is below the {} {} cubes already observed",
+                max_count, minimums[index], colours[index]
+            ));
+        }
+
+        // Each colour ranges from its observed maximum up to the search limit
+        let sizes: Vec<usize> = minimums
+            .iter()
+            .map(|min| usize::try_from(u64::from(max_count - min) + 1))
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_e| too_many_candidates())?;
+        let candidate_count =
+            combination_count(&sizes, MAX_CANDIDATES).ok_or_else(too_many_candidates)?;
+
+        // The largest bag total, which also bounds every count the factorials are needed for
+        let colour_count = u32::try_from(colours.len()).unwrap();
+        let max_total = max_count
+            .checked_mul(colour_count.max(1))
+            .ok_or_else(too_many_candidates)?;
+        let log_factorials = LogFactorials::new(max_total);
+
+        // Per colour, the summed ln(K choose k) over every round for each possible K
+        let colour_terms: Vec<Vec<f64>> = colour_histograms
+            .iter()
+            .map(|hist| {
+                (0..=max_count)
+                    .map(|bag_count| {
+                        hist.counts
+                            .iter()
+                            .map(|(drawn, times)| {
+                                f64::from(*times) * log_factorials.choose(bag_count, *drawn)
+                            })
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // The summed ln(N choose n) over every round for each possible bag total N
+        let total_terms: Vec<f64> = (0..=max_total)
+            .map(|bag_total| {
+                draw_size_histogram
+                    .counts
+                    .iter()
+                    .map(|(drawn, times)| {
+                        f64::from(*times) * log_factorials.choose(bag_total, *drawn)
+                    })
+                    .sum()
+            })
+            .collect();
+
+        let mut candidates: Vec<u32> = Vec::with_capacity(candidate_count * colours.len());
+        let mut posteriors: Vec<f64> = Vec::with_capacity(candidate_count);
+        let mut maximum_likelihood: (usize, f64) = (0, f64::NEG_INFINITY);
+
+        let mut current = minimums.clone();
+        for_each_combination(&sizes, |indices| {
+            current
+                .iter_mut()
+                .zip(minimums.iter().zip(indices.iter()))
+                .for_each(|(count, (min, index))| *count = min + *index as u32);
+            let bag_total: u32 = current.iter().sum();
+            let log_likelihood = current
+                .iter()
+                .enumerate()
+                .map(|(index, count)| colour_terms[index][*count as usize])
+                .sum::<f64>()
+                - total_terms[bag_total as usize];
+            let log_prior: f64 = match prior {
+                Prior::Uniform => 0.0,
+                Prior::Poisson(mean) => current
+                    .iter()
+                    .map(|count| f64::from(*count) * mean.ln() - mean - log_factorials.get(*count))
+                    .sum(),
+            };
+
+            if log_likelihood > maximum_likelihood.1 {
+                maximum_likelihood = (posteriors.len(), log_likelihood);
+            }
+            candidates.extend_from_slice(&current);
+            posteriors.push(log_likelihood + log_prior);
+        });
+
+        // Normalise the log posteriors in place with log-sum-exp to avoid underflow
+        let max_log_posterior = posteriors.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        posteriors
+            .iter_mut()
+            .for_each(|posterior| *posterior = (*posterior - max_log_posterior).exp());
+        let weight_total: f64 = posteriors.iter().sum();
+        posteriors
+            .iter_mut()
+            .for_each(|posterior| *posterior /= weight_total);
+
+        Ok(BagInference {
+            colours,
+            candidates,
+            maximum_likelihood_index: maximum_likelihood.0,
+            posteriors,
+        })
+    }
+
+    fn candidate(self: &Self, index: usize) -> &[u32] {
+        let stride = self.colours.len();
+        &self.candidates[index * stride..(index + 1) * stride]
+    }
+
+    fn candidates(self: &Self) -> impl Iterator<Item = &[u32]> + '_ {
+        self.candidates.chunks_exact(self.colours.len())
+    }
+
+    fn to_cube_set(self: &Self, counts: &[u32]) -> CubeSet {
+        let mut bag = CubeSet::new(0, 0, 0);
+        self.colours
+            .iter()
+            .zip(counts.iter())
+            .for_each(|(colour, count)| bag.set(colour, *count));
+        bag
+    }
+
+    fn index_of_max(values: &[f64]) -> usize {
+        values
+            .iter()
+            .enumerate()
+            .fold(0, |best, (index, value)| match *value > values[best] {
+                true => index,
+                false => best,
+            })
+    }
+
+    pub(super) fn maximum_likelihood_bag(self: &Self) -> CubeSet {
+        self.to_cube_set(self.candidate(self.maximum_likelihood_index))
+    }
+
+    pub(super) fn most_probable_bag(self: &Self) -> (CubeSet, f64) {
+        let index = Self::index_of_max(&self.posteriors);
+        (
+            self.to_cube_set(self.candidate(index)),
+            self.posteriors[index],
+        )
+    }
+
+    // Posterior probability of each count of a single colour, summed over the other colours
+    pub(super) fn marginal(self: &Self, colour: &str) -> BTreeMap<u32, f64> {
+        let mut marginal = BTreeMap::new();
+        if let Some(colour_index) = self.colours.iter().position(|name| name == colour) {
+            self.candidates()
+                .zip(self.posteriors.iter())
+                .for_each(|(candidate, posterior)| {
+                    *marginal.entry(candidate[colour_index]).or_insert(0.0) += posterior;
+                });
+        }
+        marginal
+    }
+
+    // The most probable total of each colour with its marginal posterior probability
+    pub(super) fn most_probable_counts(self: &Self) -> Vec<(String, u32, f64)> {
+        self.colours
+            .iter()
+            .map(|colour| {
+                let (count, probability) =
+                    self.marginal(colour)
+                        .into_iter()
+                        .fold((0, 0.0), |best, entry| match entry.1 > best.1 {
+                            true => entry,
+                            false => best,
+                        });
+                (colour.clone(), count, probability)
+            })
+            .collect()
+    }
+
+    pub(super) fn posterior_probability(self: &Self, bag: &CubeSet) -> f64 {
+        // A bag with cubes of a colour never considered can't match any candidate
+        if bag
+            .iter()
+            .any(|(colour, count)| count > 0 && !self.colours.iter().any(|name| name == colour))
+        {
+            return 0.0;
+        }
+
+        self.candidates()
+            .zip(self.posteriors.iter())
+            .filter(|(candidate, _)| {
+                self.colours
+                    .iter()
+                    .zip(candidate.iter())
+                    .all(|(colour, count)| *count == bag.get(colour))
+            })
+            .fold(0.0, |acc, (_, posterior)| acc + posterior)
+    }
+
+    // Probability that the true bag fits inside the hypothesised one, i.e. that the
+    // hypothesised limits are consistent with how the games were actually drawn
+    pub(super) fn probability_consistent(self: &Self, bag: &CubeSet) -> f64 {
+        self.candidates()
+            .zip(self.posteriors.iter())
+            .filter(|(candidate, _)| {
+                self.colours
+                    .iter()
+                    .zip(candidate.iter())
+                    .all(|(colour, count)| *count <= bag.get(colour))
+            })
+            .fold(0.0, |acc, (_, posterior)| acc + posterior)
+    }
+
+    pub(super) fn colours(self: &Self) -> &[String] {
+        &self.colours
+    }
+
+    pub(super) fn candidate_count(self: &Self) -> usize {
+        self.posteriors.len()
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use super::Game;
+
+// Queries are evaluated per game, e.g. `max(red) > 10 and rounds >= 4`. Supported terms are
+// numbers, `id`, `rounds` (the number of rounds), `power` (of the minimum set) and the
+// per-game aggregates `max(colour)`, `min(colour)` and `sum(colour)` over rounds. These
+// combine with `+ - *`, comparisons `< <= > >= == !=`, `and`, `or`, `not` and parentheses
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Identifier(String),
+    Symbol(String),
+    LeftParen,
+    RightParen,
+}
+
+fn tokenise(query_str: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = query_str.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+        if char.is_whitespace() {
+            index += 1;
+        } else if char.is_ascii_digit() {
+            let start = index;
+            while index < chars.len() && chars[index].is_ascii_digit() {
+                index += 1;
+            }
+            let number_str: String = chars[start..index].iter().collect();
+            let number = number_str
+                .parse::<i64>()
+                .map_err(|_e| format!("Invalid number '{}'", number_str))?;
+            tokens.push(Token::Number(number));
+        } else if char.is_ascii_alphabetic() || char == '_' {
+            let start = index;
+            while index < chars.len()
+                && (chars[index].is_ascii_alphanumeric() || chars[index] == '_')
+            {
+                index += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..index].iter().collect()));
+        } else if char == '(' {
+            tokens.push(Token::LeftParen);
+            index += 1;
+        } else if char == ')' {
+            tokens.push(Token::RightParen);
+            index += 1;
+        } else {
+            // Two character operators first, then single ones
+            let pair: String = chars[index..(index + 2).min(chars.len())].iter().collect();
+            if ["<=", ">=", "==", "!=", "&&", "||"].contains(&pair.as_str()) {
+                tokens.push(Token::Symbol(pair));
+                index += 2;
+            } else if ['<', '>', '+', '-', '*', '!'].contains(&char) {
+                tokens.push(Token::Symbol(char.to_string()));
+                index += 1;
+            } else {
+                return Err(format!("Unexpected character '{}' in query", char));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Aggregate {
+    Max,
+    Min,
+    Sum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Number(i64),
+    Id,
+    Rounds,
+    Power,
+    Aggregate(Aggregate, String),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Value {
+    Number(i64),
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Bool(bool) => write!(f, "{}", bool),
+        }
+    }
+}
+
+// Recursive descent parser, lowest precedence first: or, and, not, comparison, sum, product
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(self: &Self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(self: &mut Self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_is_keyword(self: &Self, keyword: &str, symbol: &str) -> bool {
+        match self.peek() {
+            Some(Token::Identifier(name)) => name == keyword,
+            Some(Token::Symbol(sym)) => sym == symbol,
+            _ => false,
+        }
+    }
+
+    fn expect(self: &mut Self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("Expected {:?} but found {:?}", expected, token)),
+            None => Err(format!("Expected {:?} but the query ended", expected)),
+        }
+    }
+
+    fn parse_or(self: &mut Self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.peek_is_keyword("or", "||") {
+            self.next();
+            expr = Expr::Binary(BinaryOp::Or, Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(self: &mut Self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.peek_is_keyword("and", "&&") {
+            self.next();
+            expr = Expr::Binary(BinaryOp::And, Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(self: &mut Self) -> Result<Expr, String> {
+        if self.peek_is_keyword("not", "!") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(self: &mut Self) -> Result<Expr, String> {
+        let left = self.parse_sum()?;
+        let op = match self.peek() {
+            Some(Token::Symbol(sym)) => match sym.as_str() {
+                "<" => Some(BinaryOp::Less),
+                "<=" => Some(BinaryOp::LessEqual),
+                ">" => Some(BinaryOp::Greater),
+                ">=" => Some(BinaryOp::GreaterEqual),
+                "==" => Some(BinaryOp::Equal),
+                "!=" => Some(BinaryOp::NotEqual),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match op {
+            Some(op) => {
+                self.next();
+                let right = self.parse_sum()?;
+                Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn parse_sum(self: &mut Self) -> Result<Expr, String> {
+        let mut expr = self.parse_product()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Symbol(sym)) if sym == "+" => BinaryOp::Add,
+                Some(Token::Symbol(sym)) if sym == "-" => BinaryOp::Subtract,
+                _ => return Ok(expr),
+            };
+            self.next();
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.parse_product()?));
+        }
+    }
+
+    fn parse_product(self: &mut Self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        while let Some(Token::Symbol(sym)) = self.peek() {
+            if sym != "*" {
+                break;
+            }
+            self.next();
+            expr = Expr::Binary(
+                BinaryOp::Multiply,
+                Box::new(expr),
+                Box::new(self.parse_primary()?),
+            );
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(self: &mut Self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Number(number)),
+            Some(Token::LeftParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RightParen)?;
+                Ok(expr)
+            }
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "id" => Ok(Expr::Id),
+                "rounds" => Ok(Expr::Rounds),
+                "power" => Ok(Expr::Power),
+                "max" | "min" | "sum" => {
+                    let aggregate = match name.as_str() {
+                        "max" => Aggregate::Max,
+                        "min" => Aggregate::Min,
+                        _ => Aggregate::Sum,
+                    };
+                    self.expect(Token::LeftParen)?;
+                    let colour = match self.next() {
+                        Some(Token::Identifier(colour)) => colour,
+                        _ => return Err(format!("Expected a colour inside {}()", name)),
+                    };
+                    self.expect(Token::RightParen)?;
+                    Ok(Expr::Aggregate(aggregate, colour))
+                }
+                _ => Err(format!("Unknown name '{}' in query", name)),
+            },
+            Some(token) => Err(format!("Unexpected {:?} in query", token)),
+            None => Err(String::from("Query ended unexpectedly")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub(super) fn parse(query_str: &str) -> Result<Query, String> {
+        let mut parser = Parser {
+            tokens: tokenise(query_str)?,
+            position: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected {:?} after the end of the query", token));
+        }
+        Ok(Query { expr })
+    }
+
+    pub(super) fn evaluate(self: &Self, game: &Game) -> Result<Value, String> {
+        evaluate(&self.expr, game)
+    }
+}
+
+fn evaluate_number(expr: &Expr, game: &Game) -> Result<i64, String> {
+    match evaluate(expr, game)? {
+        Value::Number(number) => Ok(number),
+        Value::Bool(_) => Err(String::from("Expected a number but found a condition")),
+    }
+}
+
+fn evaluate_bool(expr: &Expr, game: &Game) -> Result<bool, String> {
+    match evaluate(expr, game)? {
+        Value::Bool(bool) => Ok(bool),
+        Value::Number(_) => Err(String::from("Expected a condition but found a number")),
+    }
+}
+
+fn evaluate(expr: &Expr, game: &Game) -> Result<Value, String> {
+    match expr {
+        Expr::Number(number) => Ok(Value::Number(*number)),
+        Expr::Id => Ok(Value::Number(i64::from(game.id))),
+        Expr::Rounds => Ok(Value::Number(i64::try_from(game.rounds.len()).unwrap())),
+        Expr::Power => Ok(Value::Number(i64::from(
+            game.get_minimum_cube_set().get_power(),
+        ))),
+        Expr::Aggregate(aggregate, colour) => {
+            let counts = game
+                .rounds
+                .iter()
+                .map(|round| i64::from(round.result.get(colour)));
+            let value = match aggregate {
+                Aggregate::Max => counts.max().unwrap_or(0),
+                Aggregate::Min => counts.min().unwrap_or(0),
+                Aggregate::Sum => counts.sum(),
+            };
+            Ok(Value::Number(value))
+        }
+        Expr::Not(inner) => Ok(Value::Bool(!evaluate_bool(inner, game)?)),
+        Expr::Binary(op, left, right) => match op {
+            BinaryOp::And => Ok(Value::Bool(
+                evaluate_bool(left, game)? && evaluate_bool(right, game)?,
+            )),
+            BinaryOp::Or => Ok(Value::Bool(
+                evaluate_bool(left, game)? || evaluate_bool(right, game)?,
+            )),
+            _ => {
+                let left = evaluate_number(left, game)?;
+                let right = evaluate_number(right, game)?;
+                let checked = |result: Option<i64>| {
+                    result.map(Value::Number).ok_or(format!(
+                        "Arithmetic overflow evaluating the query for game {}",
+                        game.id
+                    ))
+                };
+                Ok(match op {
+                    BinaryOp::Add => checked(left.checked_add(right))?,
+                    BinaryOp::Subtract => checked(left.checked_sub(right))?,
+                    BinaryOp::Multiply => checked(left.checked_mul(right))?,
+                    BinaryOp::Less => Value::Bool(left < right),
+                    BinaryOp::LessEqual => Value::Bool(left <= right),
+                    BinaryOp::Greater => Value::Bool(left > right),
+                    BinaryOp::GreaterEqual => Value::Bool(left >= right),
+                    BinaryOp::Equal => Value::Bool(left == right),
+                    BinaryOp::NotEqual => Value::Bool(left != right),
+                    BinaryOp::And | BinaryOp::Or => unreachable!(),
+                })
+            }
+        },
+    }
+}
@@ -0,0 +1,182 @@
+use super::CubeSet;
+
+// SplitMix64, plenty for reproducible puzzle inputs without pulling in a dependency
+#[derive(Debug, Clone)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    fn next_u64(self: &mut Self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform value in `low..=high`
+    fn range_inclusive(self: &mut Self, low: usize, high: usize) -> usize {
+        let span = u64::try_from(high - low + 1).unwrap();
+        low + usize::try_from(self.next_u64() % span).unwrap()
+    }
+
+    // Uniform value in `[0, 1)` from the top 53 bits
+    fn next_f64(self: &mut Self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // How many of `successes` marked cubes turn up when drawing `draws` cubes without
+    // replacement from `population`, by inverting the hypergeometric distribution. Relative
+    // probabilities are built outwards from the mode, where they peak, so they can't overflow
+    // and the tails stop once they're too small to register
+    fn hypergeometric(self: &mut Self, population: u64, successes: u64, draws: u64) -> u64 {
+        let failures = population - successes;
+        let low = draws.saturating_sub(failures);
+        let high = draws.min(successes);
+        // P(k + 1) / P(k), only asked for with low <= k < high
+        let ratio = |k: u64| {
+            ((successes - k) as f64 * (draws - k) as f64)
+                / ((k + 1) as f64 * (failures + k + 1 - draws) as f64)
+        };
+        let mode = ((draws + 1) as f64 * (successes + 1) as f64 / (population + 2) as f64) as u64;
+        let mode = mode.clamp(low, high);
+
+        let mut below: Vec<f64> = Vec::new();
+        let mut weight = 1.0;
+        for k in (low..mode).rev() {
+            weight /= ratio(k);
+            if weight == 0.0 {
+                break;
+            }
+            below.push(weight);
+        }
+        let mut above: Vec<f64> = Vec::new();
+        weight = 1.0;
+        for k in mode..high {
+            weight *= ratio(k);
+            if weight == 0.0 {
+                break;
+            }
+            above.push(weight);
+        }
+
+        let first = mode - below.len() as u64;
+        let weights: Vec<f64> = below.into_iter().rev().chain([1.0]).chain(above).collect();
+        let mut target = self.next_f64() * weights.iter().sum::<f64>();
+        for (offset, weight) in weights.iter().enumerate() {
+            if target < *weight {
+                return first + offset as u64;
+            }
+            target -= weight;
+        }
+
+        // Only reachable through rounding in the running total
+        first + weights.len() as u64 - 1
+    }
+
+    fn shuffle<T>(self: &mut Self, values: &mut [T]) {
+        for i in (1..values.len()).rev() {
+            let j = self.range_inclusive(0, i);
+            values.swap(i, j);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct GameSimulator {
+    bag: CubeSet,
+    bag_total: u64,
+    min_rounds: u32,
+    max_rounds: u32,
+    rng: Rng,
+}
+
+impl GameSimulator {
+    pub(super) fn new(
+        bag: CubeSet,
+        min_rounds: u32,
+        max_rounds: u32,
+        seed: u64,
+    ) -> Result<GameSimulator, String> {
+        let bag_total: u64 = bag.iter().map(|(_, count)| u64::from(count)).sum();
+        if bag_total == 0 {
+            return Err(String::from("Cannot draw rounds from an empty bag"));
+        }
+
+        if min_rounds == 0 || min_rounds > max_rounds {
+            return Err(format!(
+                "Invalid rounds per game {}-{}",
+                min_rounds, max_rounds
+            ));
+        }
+
+        Ok(GameSimulator {
+            bag,
+            bag_total,
+            min_rounds,
+            max_rounds,
+            rng: Rng::new(seed),
+        })
+    }
+
+    // Draws a random number of cubes without replacement from a full bag, taking each
+    // colour's share from whatever the earlier colours left, and returns the drawn colours
+    // and counts in the (shuffled) order they should be written
+    fn draw_round(self: &mut Self) -> Vec<(String, u32)> {
+        let mut remaining_cubes = self.bag_total;
+        let mut remaining_draws = u64::try_from(
+            self.rng
+                .range_inclusive(1, usize::try_from(self.bag_total).unwrap()),
+        )
+        .unwrap();
+
+        let mut counts: Vec<(String, u32)> = self
+            .bag
+            .iter()
+            .filter_map(|(colour, count)| {
+                let drawn =
+                    self.rng
+                        .hypergeometric(remaining_cubes, u64::from(count), remaining_draws);
+                remaining_cubes -= u64::from(count);
+                remaining_draws -= drawn;
+                match drawn > 0 {
+                    // Never more than the colour's count, so it fits
+                    true => Some((colour.to_string(), u32::try_from(drawn).unwrap())),
+                    false => None,
+                }
+            })
+            .collect();
+        self.rng.shuffle(&mut counts);
+
+        counts
+    }
+
+    // Produces a game line in the same format as the puzzle input, e.g.
+    // Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    pub(super) fn simulate_game(self: &mut Self, id: u32) -> String {
+        let round_count = self
+            .rng
+            .range_inclusive(self.min_rounds as usize, self.max_rounds as usize);
+
+        let rounds: Vec<String> = (0..round_count)
+            .map(|_| {
+                self.draw_round()
+                    .iter()
+                    .map(|(colour, count)| format!("{} {}", count, colour))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            })
+            .collect();
+
+        format!("Game {}: {}", id, rounds.join("; "))
+    }
+
+    pub(super) fn simulate_games(self: &mut Self, game_count: u32) -> Vec<String> {
+        (1..=game_count).map(|id| self.simulate_game(id)).collect()
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt;
+
+use super::{parse_cube_str, split_cubes_str, split_game_str, split_rounds_str};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum IssueKind {
+    DuplicateColour(String),
+    ZeroCount(String),
+    NegativeCount(String),
+    Malformed(String),
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct ValidationIssue {
+    line_number: usize,
+    game_id: Option<u32>,
+    // Index of the round within the game, if the issue is inside one
+    round_index: Option<usize>,
+    kind: IssueKind,
+}
+
+impl ValidationIssue {
+    fn new(
+        line_number: usize,
+        game_id: Option<u32>,
+        round_index: Option<usize>,
+        kind: IssueKind,
+    ) -> ValidationIssue {
+        ValidationIssue {
+            line_number,
+            game_id,
+            round_index,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(self: &Self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}", self.line_number)?;
+        if let Some(id) = self.game_id {
+            write!(f, " (game {})", id)?;
+        }
+        if let Some(index) = self.round_index {
+            write!(f, ", round {}", index + 1)?;
+        }
+
+        match &self.kind {
+            IssueKind::DuplicateColour(colour) => {
+                write!(f, ": colour {} appears more than once", colour)
+            }
+            IssueKind::ZeroCount(colour) => write!(f, ": zero count for {}", colour),
+            IssueKind::NegativeCount(token) => write!(f, ": negative count in '{}'", token),
+            IssueKind::Malformed(message) => write!(f, ": {}", message),
+        }
+    }
+}
+
+// Checks a single `<count> <colour>` entry with the parser's own rules, returning its count
+// and colour if well formed
+fn validate_cube_str(cube_str: &str) -> Result<(u32, &str), IssueKind> {
+    parse_cube_str(cube_str).map_err(|message| match cube_str.starts_with('-') {
+        true => IssueKind::NegativeCount(cube_str.to_string()),
+        false => IssueKind::Malformed(message),
+    })
+}
+
+fn validate_round(round_str: &str) -> Vec<IssueKind> {
+    let mut issues: Vec<IssueKind> = Vec::new();
+    let mut seen_colours: Vec<&str> = Vec::new();
+
+    split_cubes_str(round_str).for_each(|cube_str| match validate_cube_str(cube_str) {
+        Ok((count, colour)) => {
+            if count == 0 {
+                issues.push(IssueKind::ZeroCount(colour.to_string()));
+            }
+            if seen_colours.contains(&colour) {
+                issues.push(IssueKind::DuplicateColour(colour.to_string()));
+            } else {
+                seen_colours.push(colour);
+            }
+        }
+        Err(kind) => issues.push(kind),
+    });
+
+    issues
+}
+
+// Lists rounds with duplicate colours, zero counts, negative looking counts or entries that
+// don't parse, without stopping at the first problem the way `Game::from_str` does. Lines
+// are split with the same functions as the parser, so any line it rejects is reported
+pub(super) fn validate_lines(lines: &[String]) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+
+    lines.iter().enumerate().for_each(|(line_index, line)| {
+        let line_number = line_index + 1;
+        let (game_id, rounds_str) = match split_game_str(line) {
+            Ok(parts) => parts,
+            Err(message) => {
+                issues.push(ValidationIssue::new(
+                    line_number,
+                    None,
+                    None,
+                    IssueKind::Malformed(message),
+                ));
+                return;
+            }
+        };
+
+        split_rounds_str(rounds_str)
+            .enumerate()
+            .for_each(|(round_index, round_str)| {
+                validate_round(round_str).into_iter().for_each(|kind| {
+                    issues.push(ValidationIssue::new(
+                        line_number,
+                        Some(game_id),
+                        Some(round_index),
+                        kind,
+                    ))
+                })
+            });
+    });
+
+    issues
+}
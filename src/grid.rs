@@ -0,0 +1,182 @@
+// Row and column offsets for the eight cells surrounding a cell
+const MOORE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+// Row and column offsets for the four cells sharing an edge with a cell
+const ORTHOGONAL_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+// Signed so that points just outside the grid (e.g. around an edge cell) can be represented
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct Point {
+    pub(crate) row: isize,
+    pub(crate) column: isize,
+}
+
+impl Point {
+    pub(crate) fn new(row: isize, column: isize) -> Point {
+        Point { row, column }
+    }
+
+    pub(crate) fn offset(self: &Self, row_offset: isize, column_offset: isize) -> Point {
+        Point::new(self.row + row_offset, self.column + column_offset)
+    }
+}
+
+// A rectangular grid stored row by row in one contiguous vector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub(crate) fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl Grid<char> {
+    // Builds a character grid from lines of text, padding short lines with `fill` so the
+    // grid stays rectangular
+    pub(crate) fn from_lines(lines: &[String], fill: char) -> Grid<char> {
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut cells: Vec<char> = Vec::with_capacity(width * lines.len());
+        lines.iter().for_each(|line| {
+            let row_start = cells.len();
+            cells.extend(line.chars());
+            cells.resize(row_start + width, fill);
+        });
+
+        Grid {
+            width,
+            height: lines.len(),
+            cells,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub(crate) fn width(self: &Self) -> usize {
+        self.width
+    }
+
+    pub(crate) fn height(self: &Self) -> usize {
+        self.height
+    }
+
+    pub(crate) fn contains(self: &Self, point: Point) -> bool {
+        point.row >= 0
+            && point.column >= 0
+            && (point.row as usize) < self.height
+            && (point.column as usize) < self.width
+    }
+
+    // Maps any point onto the grid as if its edges were joined, so a point one past the
+    // right edge lands in the first column
+    pub(crate) fn wrap(self: &Self, point: Point) -> Point {
+        Point::new(
+            point.row.rem_euclid(self.height.max(1) as isize),
+            point.column.rem_euclid(self.width.max(1) as isize),
+        )
+    }
+
+    fn index_of(self: &Self, point: Point) -> Option<usize> {
+        match self.contains(point) {
+            true => Some(point.row as usize * self.width + point.column as usize),
+            false => None,
+        }
+    }
+
+    pub(crate) fn get(self: &Self, point: Point) -> Option<&T> {
+        self.index_of(point).map(|index| &self.cells[index])
+    }
+
+    pub(crate) fn get_mut(self: &mut Self, point: Point) -> Option<&mut T> {
+        self.index_of(point).map(|index| &mut self.cells[index])
+    }
+
+    // Returns false if the point is outside the grid
+    pub(crate) fn set(self: &mut Self, point: Point, value: T) -> bool {
+        match self.get_mut(point) {
+            Some(cell) => {
+                *cell = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn row(self: &Self, row: usize) -> Option<&[T]> {
+        match row < self.height {
+            true => Some(&self.cells[row * self.width..(row + 1) * self.width]),
+            false => None,
+        }
+    }
+
+    pub(crate) fn rows(self: &Self) -> impl Iterator<Item = &[T]> + '_ {
+        // `chunks` panics on a zero size, and a zero width grid has no cells to yield anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub(crate) fn column(self: &Self, column: usize) -> impl Iterator<Item = &T> + '_ {
+        self.cells
+            .iter()
+            .skip(column)
+            .step_by(self.width.max(1))
+            .take(match column < self.width {
+                true => self.height,
+                false => 0,
+            })
+    }
+
+    // Every cell with its position, row by row
+    pub(crate) fn iter(self: &Self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.cells.iter().enumerate().map(|(index, value)| {
+            (
+                Point::new((index / self.width) as isize, (index % self.width) as isize),
+                value,
+            )
+        })
+    }
+
+    fn neighbours_with_offsets<'a>(
+        self: &'a Self,
+        point: Point,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        offsets
+            .iter()
+            .map(move |(row_offset, column_offset)| point.offset(*row_offset, *column_offset))
+            .filter_map(|neighbour| self.get(neighbour).map(|value| (neighbour, value)))
+    }
+
+    // The up to eight cells surrounding the point, including diagonals
+    pub(crate) fn neighbours(self: &Self, point: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours_with_offsets(point, &MOORE_OFFSETS)
+    }
+
+    // The up to four cells sharing an edge with the point
+    pub(crate) fn orthogonal_neighbours(
+        self: &Self,
+        point: Point,
+    ) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours_with_offsets(point, &ORTHOGONAL_OFFSETS)
+    }
+}
@@ -1,18 +1,41 @@
 #![allow(dead_code)]
+#![allow(clippy::needless_arbitrary_self_type, clippy::needless_return)]
+mod ansi;
+mod args;
 mod day_four;
 mod day_one;
 mod day_three;
 mod day_two;
+mod grid;
 
 mod inputs;
 
 fn main() {
-    // day_one::part_one();
-    // day_one::part_two();
-    // day_two::part_one();
-    // day_two::part_two();
-    // day_three::part_one();
-    // day_three::part_two();
-    day_four::part_one();
-    day_four::part_two();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("day1") => day_one::run(&args[1..]),
+        Some("day2") => day_two::run(&args[1..]),
+        Some("day3") => day_three::run(&args[1..]),
+        Some("query") => match args.get(1).map(|arg| arg.as_str()) {
+            Some("day2") => day_two::run_query(&args[2..]),
+            _ => Err(String::from("Queries are only supported for day2")),
+        },
+        _ => {
+            // day_one::part_one();
+            // day_one::part_two();
+            // day_two::part_one();
+            // day_two::part_two();
+            // day_three::part_one();
+            // day_three::part_two();
+            day_four::part_one();
+            day_four::part_two();
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 }